@@ -3,7 +3,8 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use secret_lootbox::msg::{HandleMsg, InitMsg, QueryMsg};
+use secret_lootbox::error::ContractError;
+use secret_lootbox::msg::{HandleMsg, InitMsg, QueryMsg, ReceiveMsg};
 use secret_lootbox::state::State;
 
 fn main() {
@@ -15,5 +16,7 @@ fn main() {
     export_schema(&schema_for!(InitMsg), &out_dir);
     export_schema(&schema_for!(HandleMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(ReceiveMsg), &out_dir);
     export_schema(&schema_for!(State), &out_dir);
+    export_schema(&schema_for!(ContractError), &out_dir);
 }
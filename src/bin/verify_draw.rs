@@ -0,0 +1,122 @@
+//! Recomputes the outcome of a lottery drawn from a committed seed, exactly as the contract
+//! does, so anyone can check the published result.
+//!
+//! Usage:
+//!   verify_draw <commitment> <reveal> <ticket_count> [entropy entries...]
+//!   verify_draw --lotto <pick_count> <max_number> <commitment> <reveal> [entropy entries...]
+//!   verify_draw --weighted <weights> <commitment> <reveal> [entropy entries...]
+//!
+//! `commitment` and `reveal` are the base64 values from `InitMsg` and `EndLottery`. The entropy
+//! entries are the base64 `entries` of the `DrawResult` query, in order.
+//! For a raffle, `ticket_count` is the number of tickets sold and the result is the position of
+//! the winner among them, by ticket id.
+//! For a lotto, `pick_count` and `max_number` are the ones of its game mode and the result is
+//! the winning numbers.
+//! For a weighted draw, `weights` are the `weights` of the `DrawResult` query, separated by
+//! commas, and the result is the winning ticket id.
+
+use std::env;
+use std::process;
+
+use cosmwasm_std::{Binary, Uint128};
+
+use secret_lootbox::contract::{
+    draw_numbers, draw_ticket, draw_weighted, mix_entropy, seed_matches,
+};
+
+const USAGE: &str = "usage: verify_draw [--lotto <pick_count> <max_number> | --weighted \
+                     <weights>] <commitment> <reveal> [<ticket_count>] [entropy entries...]";
+
+fn decode(name: &str, value: &str) -> Vec<u8> {
+    match Binary::from_base64(value) {
+        Ok(binary) => binary.0,
+        Err(_) => fail(&format!("{} is not valid base64", name)),
+    }
+}
+
+fn parse_positive<T: std::str::FromStr + PartialOrd + Default>(name: &str, value: &str) -> T {
+    match value.parse::<T>() {
+        Ok(number) if number > T::default() => number,
+        _ => fail(&format!("{} must be a positive integer", name)),
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("error: {}", msg);
+    process::exit(1)
+}
+
+/// Checks the reveal against the commitment and mixes the entries into the final entropy
+fn final_entropy(commitment: &str, reveal: &[u8], entries: &[String]) -> Vec<u8> {
+    let commitment = decode("commitment", commitment);
+    if !seed_matches(&commitment, reveal) {
+        fail("the revealed seed doesn't match the commitment");
+    }
+
+    let mut entropy = commitment;
+    for (i, entry) in entries.iter().enumerate() {
+        entropy = mix_entropy(&entropy, &decode(&format!("entry {}", i), entry));
+    }
+
+    println!("entropy: {}", Binary(entropy.clone()).to_base64());
+    entropy
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.first().map(String::as_str) == Some("--lotto") {
+        if args.len() < 5 {
+            fail(USAGE);
+        }
+
+        let pick_count: u8 = parse_positive("pick_count", &args[1]);
+        let max_number: u8 = parse_positive("max_number", &args[2]);
+        if pick_count > max_number {
+            fail("pick_count can't be greater than max_number");
+        }
+
+        let reveal = decode("reveal", &args[4]);
+        let entropy = final_entropy(&args[3], &reveal, &args[5..]);
+        let numbers: Vec<String> = draw_numbers(&reveal, &entropy, pick_count, max_number)
+            .iter()
+            .map(|number| number.to_string())
+            .collect();
+        println!("winning numbers: {}", numbers.join(" "));
+        return;
+    }
+
+    if args.first().map(String::as_str) == Some("--weighted") {
+        if args.len() < 4 {
+            fail(USAGE);
+        }
+
+        let mut weights = vec![];
+        for weight in args[1].split(',') {
+            match weight.trim().parse::<u128>() {
+                Ok(weight) => weights.push(Uint128(weight)),
+                Err(_) => fail("weights must be integers separated by commas"),
+            }
+        }
+
+        let reveal = decode("reveal", &args[3]);
+        let entropy = final_entropy(&args[2], &reveal, &args[4..]);
+        match draw_weighted(&reveal, &entropy, &weights) {
+            Some(ticket_id) => println!("winning ticket: {}", ticket_id),
+            None => println!("no winner, every weight is 0"),
+        }
+        return;
+    }
+
+    if args.len() < 3 {
+        fail(USAGE);
+    }
+
+    let reveal = decode("reveal", &args[1]);
+    let ticket_count: u32 = parse_positive("ticket_count", &args[2]);
+    let entropy = final_entropy(&args[0], &reveal, &args[3..]);
+    println!(
+        "golden ticket: {}",
+        draw_ticket(&reveal, &entropy, ticket_count)
+    );
+}
@@ -1,56 +1,451 @@
 use cosmwasm_std::{
-    coin, to_binary, Api, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Env, Extern,
-    HandleResponse, HumanAddr, InitResponse, Querier, StdError, StdResult, Storage, Uint128,
+    from_binary, log, to_binary, Api, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Env, Extern,
+    HandleResponse, HumanAddr, InitResponse, LogAttribute, Querier, StdResult, Storage, Uint128,
+    WasmMsg,
 };
 use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
 
-use crate::msg::{HandleMsg, InitMsg, QueryMsg};
-use crate::state::{config, config_read, State, Ticket, USCRT_DENOM};
+use crate::error::ContractError;
+use crate::msg::{
+    BeneficiaryInfo, BeneficiaryPayoutResponse, DrawResultResponse, HandleMsg, InfoResponse,
+    InitMsg, ListingResponse, OfferResponse, PendingPayoutsResponse, QueryMsg, ReceiveMsg,
+    RevealResponse, Snip20Msg, UnclaimedPrize, UnclaimedResponse, WinningTicketResponse,
+};
+use crate::state::{
+    config, config_read, draw_result, draw_result_read, sealed, sealed_read, viewing_keys,
+    viewing_keys_read, Beneficiary, BeneficiaryPayout, DrawResult, GameMode, Listing, Loot,
+    LotteryStatus, LottoTier, Offer, ScratchTier, SealedTickets, State, Ticket, WinningTicket,
+    USCRT_DENOM,
+};
 
 lazy_static! {
     static ref ZERO_ADDRESS: CanonicalAddr = CanonicalAddr(Binary(vec![0; 8]));
 }
 
+/// Upper bound on the tickets a single lottery can hold, since all of them live in `State`
+pub const MAX_TICKET_COUNT: u32 = 10_000;
+/// Upper bound on the tickets processed by a single `SettleBatch`
+pub const MAX_SETTLE_BATCH: u32 = 500;
+/// Blocks after `draw_at` the host has to reveal a committed seed, by default
+pub const DEFAULT_REVEAL_WINDOW: u64 = 14_400;
+/// Scratch card odds are expressed in parts per million
+pub const ODDS_DENOMINATOR: u32 = 1_000_000;
+/// Upper bound on the beneficiaries, since every one of them is paid in the draw
+pub const MAX_BENEFICIARIES: u32 = 5;
+
 pub fn init<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     msg: InitMsg,
 ) -> StdResult<InitResponse> {
-    if env.message.sent_funds.is_empty() {
-        return Err(throw_gen_err(format!("You have to send a winning prize")));
-    }
+    validate_init(&env, &msg)?;
 
-    // Init msg.item_count items
-    let mut items = Vec::<Ticket>::new();
-    for i in 0..msg.ticket_count {
-        items.push(Ticket {
-            id: i,
-            value: coin(1, USCRT_DENOM.clone()),
-            owner: env.contract.address.clone(),
-            approved: Vec::<CanonicalAddr>::new(),
-        });
-    }
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    // SNIP-20 lotteries are funded later through `Receive`, so the token's address is used
+    // as the denom of every ticket value. Native lotteries are funded with the init funds.
+    let (denom, token, prize_amount, deposit) = match &msg.token {
+        Some(token) => {
+            if !env.message.sent_funds.is_empty() {
+                return Err(ContractError::UnexpectedFunds {}.into());
+            }
+
+            let prize_amount = msg.prize.unwrap_or(Uint128(0));
+            if prize_amount.u128() == 0 {
+                return Err(ContractError::ZeroPrize {}.into());
+            }
+
+            // Ask the token to call us back on `Send`
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.address.clone(),
+                msg: to_binary(&Snip20Msg::RegisterReceive {
+                    code_hash: token.code_hash.clone(),
+                })?,
+                send: vec![],
+            }));
+
+            (
+                token.address.as_str().to_string(),
+                Some(deps.api.canonical_address(&token.address)?),
+                prize_amount,
+                Uint128(0),
+            )
+        }
+        None => {
+            let denom = msg.denom.clone().unwrap_or_else(|| USCRT_DENOM.to_string());
+            validate_native_prize(&env, &denom)?;
+
+            let amount = env.message.sent_funds[0].amount;
+            (denom, None, amount, amount)
+        }
+    };
 
-    // Building the winning representation as a coin
+    let mode = msg.mode.clone().unwrap_or(GameMode::Classic);
+
+    // Building the winning representation as a coin. In lootbox mode this is the whole pool
+    // the loot is paid from.
     let winning_prize = Coin {
-        denom: USCRT_DENOM.to_string(),
-        amount: env.message.sent_funds[0].amount,
+        denom: denom.clone(),
+        amount: prize_amount,
+    };
+
+    // Ticket values are kept apart from the tickets, see `SealedTickets`
+    let mut items = Vec::<Ticket>::new();
+    let mut sealed_tickets = SealedTickets {
+        values: vec![],
+        golden: None,
+        picks: vec![],
+        weights: vec![],
+        paid: vec![],
+        buyers: vec![],
+        entries: vec![],
     };
+    if mode == GameMode::Classic {
+        // Init msg.item_count items
+        for i in 0..msg.ticket_count {
+            items.push(Ticket {
+                id: i,
+                owner: env.contract.address.clone(),
+                approved: Vec::<CanonicalAddr>::new(),
+            });
+            sealed_tickets.values.push(Uint128(0));
+            sealed_tickets.paid.push(Uint128(0));
+        }
+
+        // With a committed seed the golden ticket is only picked at the draw
+        if msg.seed_commitment.is_none() {
+            sealed_tickets.values[msg.golden as usize] = prize_amount;
+            sealed_tickets.golden = Some(msg.golden);
+        }
+    }
 
-    items[msg.golden as usize].value = winning_prize.clone();
+    let loot_issued = match &mode {
+        GameMode::Lootbox { loot_table } => {
+            validate_loot_table(loot_table, prize_amount)?;
+            vec![0; loot_table.len()]
+        }
+        GameMode::ScratchCard { tiers } => {
+            validate_scratch_tiers(tiers, prize_amount)?;
+            vec![]
+        }
+        GameMode::Lotto {
+            pick_count,
+            max_number,
+            tiers,
+        } => {
+            validate_lotto(*pick_count, *max_number, tiers)?;
+            vec![]
+        }
+        _ => vec![],
+    };
 
     // Create state
     let state = State {
+        name: msg.name.clone(),
         items,
         contract_owner: env.message.sender.clone(),
+        contract_addr: env.contract.address.clone(),
         winning_prize: winning_prize.clone(),
-        deposit: env.message.sent_funds[0].amount,
+        deposit,
+        status: LotteryStatus::Open,
+        host_remainder: Uint128(0),
+        settle_cursor: 0,
+        drawn_at: None,
+        claim_window: msg.claim_window,
+        treasury: match &msg.treasury {
+            Some(treasury) => Some(deps.api.canonical_address(treasury)?),
+            None => None,
+        },
+        prize_funded: token.is_none(),
+        denom,
+        token,
+        listings: vec![],
+        offers: vec![],
+        royalty_bps: msg.royalty_bps.unwrap_or(0),
+        draw_at: msg.draw_at,
+        freeze_blocks: msg.freeze_blocks.unwrap_or(0),
+        royalty_recipient: match &msg.royalty_recipient {
+            Some(recipient) => Some(deps.api.canonical_address(recipient)?),
+            None => None,
+        },
+        entropy: msg
+            .seed_commitment
+            .clone()
+            .unwrap_or_else(|| Binary(vec![])),
+        seed_commitment: msg.seed_commitment,
+        mode,
+        ticket_price: msg.ticket_price.unwrap_or(Uint128(1)),
+        prng_seed: msg.prng_seed.unwrap_or_else(|| Binary(vec![])),
+        loot_issued,
+        reveal_after_close: msg.reveal_after_close.unwrap_or(false),
+        instant_awarded: Uint128(0),
+        min_tickets_sold: msg.min_tickets_sold.unwrap_or(0),
+        consolation_bps: msg.consolation_bps.unwrap_or(0),
+        reveal_window: msg.reveal_window.unwrap_or(DEFAULT_REVEAL_WINDOW),
+        beneficiaries: match &msg.beneficiaries {
+            Some(beneficiaries) => {
+                let mut canonical = vec![];
+                for beneficiary in beneficiaries {
+                    canonical.push(Beneficiary {
+                        address: deps.api.canonical_address(&beneficiary.address)?,
+                        bps: beneficiary.bps,
+                    });
+                }
+                canonical
+            }
+            None => vec![],
+        },
     };
 
     // Save to state
     config(&mut deps.storage).save(&state)?;
+    sealed(&mut deps.storage).save(&sealed_tickets)?;
+
+    Ok(InitResponse {
+        messages,
+        log: vec![],
+    })
+}
+
+fn validate_init(env: &Env, msg: &InitMsg) -> StdResult<()> {
+    // Only the classic mode creates its tickets upfront
+    if msg.mode.is_none() || msg.mode == Some(GameMode::Classic) {
+        if msg.ticket_count == 0 || msg.ticket_count > MAX_TICKET_COUNT {
+            return Err(ContractError::InvalidTicketCount {
+                count: msg.ticket_count,
+                max: MAX_TICKET_COUNT,
+            }
+            .into());
+        }
+
+        if msg.golden >= msg.ticket_count {
+            return Err(ContractError::InvalidGolden {
+                golden: msg.golden,
+                ticket_count: msg.ticket_count,
+            }
+            .into());
+        }
+
+        // Otherwise the lottery could only ever be refunded
+        if msg.min_tickets_sold.unwrap_or(0) > msg.ticket_count {
+            return Err(ContractError::InvalidMinTickets {
+                min_tickets_sold: msg.min_tickets_sold.unwrap_or(0),
+                ticket_count: msg.ticket_count,
+            }
+            .into());
+        }
+    }
+
+    if msg.ticket_price == Some(Uint128(0)) {
+        return Err(ContractError::ZeroPrice {}.into());
+    }
+
+    match &msg.mode {
+        // Lootboxes are filled from the contract's private randomness
+        Some(GameMode::Lootbox { .. }) => {
+            if msg.prng_seed.is_none() {
+                return Err(ContractError::MissingPrngSeed {}.into());
+            }
+        }
+        // Scratch cards too, but the seed has to be committed since it's published at the end
+        Some(GameMode::ScratchCard { .. }) => match (&msg.prng_seed, &msg.seed_commitment) {
+            (Some(seed), Some(commitment)) => {
+                if !seed_matches(commitment.as_slice(), seed.as_slice()) {
+                    return Err(ContractError::InvalidReveal {}.into());
+                }
+            }
+            _ => return Err(ContractError::MissingPrngSeed {}.into()),
+        },
+        // Lotto numbers, raffle and weighted winners are drawn from a committed seed
+        Some(GameMode::Lotto { .. }) | Some(GameMode::Raffle) | Some(GameMode::Weighted) => {
+            if msg.seed_commitment.is_none() {
+                return Err(ContractError::MissingSeedCommitment {}.into());
+            }
+        }
+        _ => {}
+    }
+
+    // Instant wins can't be taken back, nor known to lose before the draw
+    if msg.min_tickets_sold.unwrap_or(0) > 0 || msg.consolation_bps.unwrap_or(0) > 0 {
+        match msg.mode {
+            Some(GameMode::Lootbox { .. }) | Some(GameMode::ScratchCard { .. }) => {
+                return Err(ContractError::WrongMode {}.into());
+            }
+            _ => {}
+        }
+    }
+
+    // A committed draw needs a fixed height, or the host could end it whenever the entropy
+    // suits them
+    let mode = msg.mode.clone().unwrap_or(GameMode::Classic);
+    if msg.seed_commitment.is_some() && draws_from_reveal(&mode) && msg.draw_at.is_none() {
+        return Err(ContractError::MissingDrawHeight {}.into());
+    }
+
+    // The freeze is counted back from the draw height
+    if msg.freeze_blocks.unwrap_or(0) > 0 && msg.draw_at.is_none() {
+        return Err(ContractError::FreezeWithoutDrawHeight {}.into());
+    }
+
+    // Otherwise the lottery could be cancelled right away, before anyone had a chance to buy
+    if msg.min_tickets_sold.unwrap_or(0) > 0 && msg.draw_at.is_none() {
+        return Err(ContractError::MinTicketsWithoutDrawHeight {}.into());
+    }
+
+    if let Some(draw_at) = msg.draw_at {
+        if draw_at <= env.block.height {
+            return Err(ContractError::InvalidDrawHeight { draw_at }.into());
+        }
+    }
+
+    if msg.royalty_bps.unwrap_or(0) > 10_000 {
+        return Err(ContractError::InvalidBasisPoints {
+            bps: msg.royalty_bps.unwrap_or(0),
+        }
+        .into());
+    }
+
+    // Beneficiaries can't share more than the whole remainder
+    if let Some(beneficiaries) = &msg.beneficiaries {
+        if beneficiaries.len() > MAX_BENEFICIARIES as usize {
+            return Err(ContractError::TooManyBeneficiaries {
+                count: beneficiaries.len() as u32,
+                max: MAX_BENEFICIARIES,
+            }
+            .into());
+        }
+
+        let total: u32 = beneficiaries.iter().map(|b| b.bps as u32).sum();
+        if total > 10_000 {
+            return Err(ContractError::InvalidBasisPoints {
+                bps: total.min(u16::MAX as u32) as u16,
+            }
+            .into());
+        }
+    }
+
+    if msg.consolation_bps.unwrap_or(0) > 10_000 {
+        return Err(ContractError::InvalidBasisPoints {
+            bps: msg.consolation_bps.unwrap_or(0),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Every loot has to be winnable, and the pool has to cover the whole supply of loot
+fn validate_loot_table(loot_table: &[Loot], pool: Uint128) -> StdResult<()> {
+    if loot_table.is_empty() {
+        return Err(ContractError::InvalidLootTable {}.into());
+    }
+
+    let mut liability = Uint128(0);
+    let mut supply: u32 = 0;
+    for loot in loot_table {
+        if loot.weight == 0 || loot.supply == 0 {
+            return Err(ContractError::InvalidLootTable {}.into());
+        }
+
+        let total = match loot.amount.u128().checked_mul(loot.supply as u128) {
+            Some(total) => Uint128(total),
+            None => return Err(ContractError::Overflow {}.into()),
+        };
+        liability = checked_add(liability, total)?;
+        supply = supply.saturating_add(loot.supply);
+    }
+
+    if supply > MAX_TICKET_COUNT {
+        return Err(ContractError::InvalidTicketCount {
+            count: supply,
+            max: MAX_TICKET_COUNT,
+        }
+        .into());
+    }
+
+    if liability.u128() > pool.u128() {
+        return Err(ContractError::PayoutExceedsDeposit {
+            payout: liability,
+            deposit: pool,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Every tier has to pay something the pool can cover, and together the odds can't go over
+/// certainty
+fn validate_scratch_tiers(tiers: &[ScratchTier], pool: Uint128) -> StdResult<()> {
+    if tiers.is_empty() {
+        return Err(ContractError::InvalidScratchTiers {}.into());
+    }
+
+    let mut total_odds: u32 = 0;
+    for tier in tiers {
+        if tier.amount.u128() == 0 || tier.amount.u128() > pool.u128() {
+            return Err(ContractError::InvalidScratchTiers {}.into());
+        }
+        total_odds = total_odds.saturating_add(tier.odds);
+    }
+
+    if total_odds > ODDS_DENOMINATOR {
+        return Err(ContractError::InvalidScratchTiers {}.into());
+    }
+
+    Ok(())
+}
+
+/// Tiers need a number of matches a ticket can have, at most one tier for each, and together
+/// they can't take more than the whole pool
+fn validate_lotto(pick_count: u8, max_number: u8, tiers: &[LottoTier]) -> StdResult<()> {
+    if pick_count == 0 || max_number < pick_count {
+        return Err(ContractError::InvalidPicks {
+            pick_count,
+            max_number,
+        }
+        .into());
+    }
+
+    if tiers.is_empty() {
+        return Err(ContractError::InvalidLottoTiers {}.into());
+    }
+
+    let mut total_bps: u32 = 0;
+    for (i, tier) in tiers.iter().enumerate() {
+        if tier.matches == 0 || tier.matches > pick_count {
+            return Err(ContractError::InvalidLottoTiers {}.into());
+        }
+        if tiers[..i].iter().any(|other| other.matches == tier.matches) {
+            return Err(ContractError::InvalidLottoTiers {}.into());
+        }
+        total_bps += tier.share_bps as u32;
+    }
+
+    if total_bps > 10_000 {
+        return Err(ContractError::InvalidLottoTiers {}.into());
+    }
+
+    Ok(())
+}
+
+/// The prize has to be sent as exactly one coin of the lottery's denom
+fn validate_native_prize(env: &Env, denom: &str) -> StdResult<()> {
+    match env.message.sent_funds.len() {
+        0 => return Err(ContractError::NoFunds {}.into()),
+        1 => {}
+        _ => return Err(ContractError::MultipleCoins {}.into()),
+    }
+
+    validate_denom(&env.message.sent_funds, denom)?;
+
+    let prize = &env.message.sent_funds[0];
+    if prize.amount.u128() == 0 {
+        return Err(ContractError::ZeroPrize {}.into());
+    }
 
-    Ok(InitResponse::default())
+    Ok(())
 }
 
 pub fn handle<S: Storage, A: Api, Q: Querier>(
@@ -59,11 +454,40 @@ pub fn handle<S: Storage, A: Api, Q: Querier>(
     msg: HandleMsg,
 ) -> StdResult<HandleResponse> {
     match msg {
-        HandleMsg::SafeTransferFrom { from, to, ticket_id } => {
-            safe_transfer_from(deps, env, &from, &to, ticket_id)
+        HandleMsg::SafeTransferFrom {
+            from,
+            to,
+            ticket_id,
+        } => safe_transfer_from(deps, env, &from, &to, ticket_id),
+        HandleMsg::BuyTicket { ticket_id, entropy } => buy_ticket(deps, env, ticket_id, entropy),
+        HandleMsg::BuyLootbox { entropy } => buy_lootbox(deps, env, entropy),
+        HandleMsg::OpenLootbox { ticket_id } => open_lootbox(deps, env, ticket_id),
+        HandleMsg::BuyScratchCard { entropy } => buy_scratch_card(deps, env, entropy),
+        HandleMsg::BuyLottoTicket { numbers, entropy } => {
+            buy_lotto_ticket(deps, env, numbers, entropy)
         }
-        HandleMsg::BuyTicket { ticket_id } => buy_ticket(deps, env, ticket_id),
-        HandleMsg::EndLottery {} => end_lottery(deps, env),
+        HandleMsg::BuyRaffleTicket { entropy } => buy_raffle_ticket(deps, env, entropy),
+        HandleMsg::Contribute { entropy } => contribute(deps, env, entropy),
+        HandleMsg::RevealTicket { ticket_id } => handle_reveal_ticket(deps, env, ticket_id),
+        HandleMsg::EndLottery { reveal } => end_lottery(deps, env, reveal),
+        HandleMsg::ClaimPrize { ticket_ids } => claim_prize(deps, env, ticket_ids),
+        HandleMsg::ClaimRemainder {} => claim_remainder(deps, env),
+        HandleMsg::ClaimRefund { ticket_ids } => claim_refund(deps, env, ticket_ids),
+        HandleMsg::SettleBatch { limit } => settle_batch(deps, env, limit),
+        HandleMsg::SweepUnclaimed {} => sweep_unclaimed(deps, env),
+        HandleMsg::SetViewingKey { key } => set_viewing_key(deps, env, &key),
+        HandleMsg::ListTicket { ticket_id, price } => list_ticket(deps, env, ticket_id, price),
+        HandleMsg::Delist { ticket_id } => delist(deps, env, ticket_id),
+        HandleMsg::BuyListed { ticket_id } => buy_listed(deps, env, ticket_id),
+        HandleMsg::MakeOffer { ticket_id } => make_offer(deps, env, ticket_id),
+        HandleMsg::CancelOffer { ticket_id } => cancel_offer(deps, env, ticket_id),
+        HandleMsg::AcceptOffer { ticket_id } => accept_offer(deps, env, ticket_id),
+        HandleMsg::Receive {
+            sender: _,
+            from,
+            amount,
+            msg,
+        } => receive(deps, env, &from, amount, msg),
     }
 }
 
@@ -74,13 +498,97 @@ pub fn query<S: Storage, A: Api, Q: Querier>(
     match msg {
         QueryMsg::BalanceOf { owner } => to_binary(&balance_of(deps, &owner)),
         QueryMsg::OwnerOf { ticket_id } => to_binary(&owner_of(deps, ticket_id)),
+        QueryMsg::UnclaimedPrizes {} => to_binary(&unclaimed_prizes(deps)?),
+        QueryMsg::PendingPayouts {
+            address,
+            viewing_key,
+        } => to_binary(&pending_payouts(deps, &address, &viewing_key)?),
+        QueryMsg::DrawResult {} => to_binary(&query_draw_result(deps)?),
+        QueryMsg::Listings {} => to_binary(&listings(deps)?),
+        QueryMsg::Offers {} => to_binary(&offers(deps)?),
+        QueryMsg::Info {} => to_binary(&info(deps)?),
+        QueryMsg::RevealTicket {
+            ticket_id,
+            viewing_key,
+        } => to_binary(&query_reveal_ticket(deps, ticket_id, &viewing_key)?),
+    }
+}
+
+fn check_price(state: &State, amount: Uint128) -> StdResult<()> {
+    if amount.u128() < state.ticket_price.u128() {
+        return Err(ContractError::InsufficientFunds {
+            required: state.ticket_price,
+            sent: amount,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// SNIP-20 lotteries are only paid through `Receive`, native funds sent to them would be lost
+fn check_native(state: &State) -> StdResult<()> {
+    if state.token.is_some() {
+        return Err(ContractError::UnexpectedFunds {}.into());
+    }
+
+    Ok(())
+}
+
+/// Native payments are exactly one coin of the lottery's denom, returns its amount
+fn single_payment(env: &Env, state: &State) -> StdResult<Uint128> {
+    check_native(state)?;
+
+    match env.message.sent_funds.len() {
+        0 => return Err(ContractError::NoFunds {}.into()),
+        1 => {}
+        _ => return Err(ContractError::MultipleCoins {}.into()),
+    }
+    validate_denom(&env.message.sent_funds, &state.denom)?;
+
+    Ok(env.message.sent_funds[0].amount)
+}
+
+/// Don't sell anything before the host has funded the prize. Prizes paid during sales lower
+/// the deposit, so the flag is checked rather than the deposit.
+fn check_funded(state: &State) -> StdResult<()> {
+    if !state.prize_funded {
+        return Err(ContractError::NotFunded {}.into());
+    }
+
+    Ok(())
+}
+
+/// Makes sure every coin sent is of the lottery's denom
+fn validate_denom(sent_funds: &[Coin], denom: &str) -> StdResult<()> {
+    for sent in sent_funds {
+        if sent.denom != denom {
+            return Err(ContractError::WrongDenom {
+                expected: denom.to_string(),
+                got: sent.denom.clone(),
+            }
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+fn checked_add(a: Uint128, b: Uint128) -> StdResult<Uint128> {
+    match a.u128().checked_add(b.u128()) {
+        Some(sum) => Ok(Uint128(sum)),
+        None => Err(ContractError::Overflow {}.into()),
     }
 }
 
-fn throw_gen_err(msg: String) -> StdError {
-    StdError::GenericErr {
-        msg,
-        backtrace: None,
+fn checked_sub(a: Uint128, b: Uint128) -> StdResult<Uint128> {
+    match a.u128().checked_sub(b.u128()) {
+        Some(diff) => Ok(Uint128(diff)),
+        None => Err(ContractError::PayoutExceedsDeposit {
+            payout: b,
+            deposit: a,
+        }
+        .into()),
     }
 }
 
@@ -88,6 +596,66 @@ fn is_owner_or_approved(item: &Ticket, addr: &CanonicalAddr) -> bool {
     addr == &item.owner || item.approved.clone().iter().any(|i| i == addr)
 }
 
+/// Adds `amount` to the payout of `to`, so each recipient gets a single message. Recipients
+/// keep the order they were first seen in, and zero amounts are dropped.
+fn add_payout(
+    payouts: &mut Vec<(CanonicalAddr, Uint128)>,
+    to: &CanonicalAddr,
+    amount: Uint128,
+) -> StdResult<()> {
+    if amount.u128() == 0 {
+        return Ok(());
+    }
+
+    match payouts.iter_mut().find(|(owner, _)| owner == to) {
+        Some((_, total)) => *total = checked_add(*total, amount)?,
+        None => payouts.push((to.clone(), amount)),
+    }
+
+    Ok(())
+}
+
+/// Tickets can't change hands during the last `freeze_blocks` blocks before `draw_at`
+fn check_not_frozen(state: &State, env: &Env) -> StdResult<()> {
+    if let Some(draw_at) = state.draw_at {
+        let frozen_from = draw_at.saturating_sub(state.freeze_blocks);
+        if state.freeze_blocks > 0 && env.block.height >= frozen_from {
+            return Err(ContractError::TransfersFrozen { frozen_from }.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Tickets are sold until the lottery is drawn, and never from `draw_at` on, so the entropy of
+/// the draw is fixed before anyone can end it
+fn check_sales_open(state: &State, env: &Env) -> StdResult<()> {
+    if state.status != LotteryStatus::Open {
+        return Err(ContractError::LotteryClosed {}.into());
+    }
+
+    if let Some(draw_at) = state.draw_at {
+        if env.block.height >= draw_at {
+            return Err(ContractError::LotteryClosed {}.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the outcome is drawn from a committed seed the host reveals with `EndLottery`
+fn draws_from_reveal(mode: &GameMode) -> bool {
+    match mode {
+        GameMode::Classic | GameMode::Lotto { .. } | GameMode::Raffle | GameMode::Weighted => true,
+        GameMode::Lootbox { .. } | GameMode::ScratchCard { .. } => false,
+    }
+}
+
+/// A ticket is sold while someone other than the contract holds it and it wasn't redeemed
+fn is_sold(item: &Ticket, contract_addr: &CanonicalAddr) -> bool {
+    &item.owner != contract_addr && item.owner != *ZERO_ADDRESS
+}
+
 fn is_token_id_valid(token_id: u32, state: &State) -> bool {
     (token_id as usize) < state.items.len()
 }
@@ -99,6 +667,10 @@ fn perform_transfer<S: Storage, A: Api, Q: Querier>(
 ) -> StdResult<State> {
     config(&mut deps.storage).update(|mut state| {
         state.items[token_id as usize].owner = to.clone();
+        // A listing is only valid for the owner who made it
+        state
+            .listings
+            .retain(|listing| listing.ticket_id != token_id);
         Ok(state)
     })
 }
@@ -107,136 +679,2153 @@ fn buy_ticket<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
     token_id: u32,
+    entropy: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let amount = single_payment(&env, &state)?;
+
+    perform_purchase(deps, &env, &env.message.sender, amount, token_id, entropy)
+}
+
+/// Sells a ticket still held by the contract to `buyer` for `amount`. The denom of the payment
+/// must be validated by the caller.
+fn perform_purchase<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    buyer: &CanonicalAddr,
+    amount: Uint128,
+    token_id: u32,
+    entropy: Option<Binary>,
 ) -> StdResult<HandleResponse> {
-    if env.message.sent_funds.is_empty() {
-        return Err(throw_gen_err(format!("You can't get tickets for free!")));
+    let state = config_read(&deps.storage).load()?;
+
+    check_sales_open(&state, env)?;
+
+    if state.mode != GameMode::Classic {
+        return Err(ContractError::WrongMode {}.into());
+    }
+
+    check_price(&state, amount)?;
+
+    if !is_token_id_valid(token_id, &state) {
+        return Err(ContractError::InvalidTicket {
+            ticket_id: token_id,
+        }
+        .into());
     }
-    let sent_funds: Coin = env.message.sent_funds[0].clone();
 
-    // TODO min entry price in state
-    if sent_funds.amount.u128() < 1 {
-        return Err(throw_gen_err(format!(
-            "You sent {:?} funds, it is not enough!",
-            sent_funds.amount
-        )));
+    // Only tickets still held by the contract are for sale
+    if state.items[token_id as usize].owner != env.contract.address {
+        return Err(ContractError::TicketSold {
+            ticket_id: token_id,
+        }
+        .into());
     }
 
+    // Every purchase is mixed into the entropy of the draw, see `entropy_entry`
+    let entry = entropy_entry(buyer, token_id, env.block.height, &entropy);
+
+    // The whole payment is tracked, whatever exceeds the ticket's value goes to the host
     config(&mut deps.storage).update(|mut state| {
-        state.deposit.0 += 1;
+        state.deposit = checked_add(state.deposit, amount)?;
+        state.entropy = Binary(mix_entropy(state.entropy.as_slice(), &entry));
         Ok(state)
     })?;
 
+    sealed(&mut deps.storage).update(|mut sealed_tickets| {
+        sealed_tickets.paid[token_id as usize] = amount;
+        sealed_tickets.entries.push(Binary(entry));
+        Ok(sealed_tickets)
+    })?;
+
     // Transfer coin to buyer
-    perform_transfer(deps, &env.message.sender, token_id)?;
+    perform_transfer(deps, buyer, token_id)?;
 
-    Ok(HandleResponse {
-        messages: vec![],
-        log: vec![],
-        data: None,
-    })
+    Ok(HandleResponse::default())
 }
 
-fn end_lottery<S: Storage, A: Api, Q: Querier>(
+fn buy_lootbox<S: Storage, A: Api, Q: Querier>(
     deps: &mut Extern<S, A, Q>,
     env: Env,
+    entropy: Option<Binary>,
 ) -> StdResult<HandleResponse> {
-    // TODO Check if contract has expired
+    let state = config_read(&deps.storage).load()?;
+    let amount = single_payment(&env, &state)?;
+    perform_buy_lootbox(deps, &env, &env.message.sender, amount, entropy)
+}
 
+/// Mints a lootbox for `buyer` holding a prize drawn from the loot table. The response only
+/// carries the box's id, its content stays private until it's opened.
+fn perform_buy_lootbox<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    buyer: &CanonicalAddr,
+    amount: Uint128,
+    entropy: Option<Binary>,
+) -> StdResult<HandleResponse> {
     let mut state = config(&mut deps.storage).load()?;
-    let mut messages: Vec<CosmosMsg> = vec![];
-    let contract_addr: HumanAddr = deps.api.human_address(&env.contract.address)?;
 
-    for item in state.items.clone() {
-        let to: HumanAddr = deps.api.human_address(&item.owner)?;
+    check_sales_open(&state, env)?;
 
-        if to == contract_addr {
-            continue;
-        }
+    let loot_table = match &state.mode {
+        GameMode::Lootbox { loot_table } => loot_table.clone(),
+        _ => return Err(ContractError::WrongMode {}.into()),
+    };
 
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: contract_addr.clone(),
-            to_address: to.clone(),
-            amount: vec![item.value.clone()],
-        }));
+    check_price(&state, amount)?;
 
-        state.deposit.0 -= item.value.amount.u128();
-        state.items[item.id as usize].owner = (*ZERO_ADDRESS).clone();
-    }
+    let ticket_id = state.items.len() as u32;
+    let entry = entropy_entry(buyer, ticket_id, env.block.height, &entropy);
+    state.entropy = Binary(mix_entropy(state.entropy.as_slice(), &entry));
 
-    let owner_addr = deps.api.human_address(&state.contract_owner)?;
+    // Weighted pick among the loot that isn't sold out
+    let mut total_weight: u64 = 0;
+    for (i, loot) in loot_table.iter().enumerate() {
+        if state.loot_issued[i] < loot.supply {
+            total_weight += loot.weight as u64;
+        }
+    }
+    if total_weight == 0 {
+        return Err(ContractError::SoldOut {}.into());
+    }
 
-    // If anything left in the deposit, return to contract owner
-    if state.deposit.u128() > 0 {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            from_address: contract_addr.clone(),
-            to_address: owner_addr,
-            amount: vec![Coin {
-                denom: USCRT_DENOM.to_string(),
-                amount: state.deposit,
-            }],
-        }));
+    let mut roll = private_random(&state) % total_weight;
+    let mut picked = 0;
+    for (i, loot) in loot_table.iter().enumerate() {
+        if state.loot_issued[i] >= loot.supply {
+            continue;
+        }
+        if roll < loot.weight as u64 {
+            picked = i;
+            break;
+        }
+        roll -= loot.weight as u64;
     }
 
-    // Save state
+    state.loot_issued[picked] += 1;
+    state.deposit = checked_add(state.deposit, amount)?;
+    state.items.push(Ticket {
+        id: ticket_id,
+        owner: buyer.clone(),
+        approved: vec![],
+    });
     config(&mut deps.storage).save(&state)?;
 
-    // TODO mark lottery as ended
+    sealed(&mut deps.storage).update(|mut sealed_tickets| {
+        sealed_tickets.values.push(loot_table[picked].amount);
+        sealed_tickets.entries.push(Binary(entry));
+        Ok(sealed_tickets)
+    })?;
 
     Ok(HandleResponse {
-        messages,
-        log: vec![],
-        data: None,
+        messages: vec![],
+        log: vec![log("ticket_id", ticket_id)],
+        data: Some(to_binary(&ticket_id)?),
     })
 }
 
-// ERC-721 interface
+fn buy_scratch_card<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    entropy: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let amount = single_payment(&env, &state)?;
+    perform_buy_scratch_card(deps, &env, &env.message.sender, amount, entropy)
+}
 
-/// @dev This emits when ownership of any NFT changes by any mechanism.
-///  This event emits when NFTs are created (`from` == 0) and destroyed
-///  (`to` == 0). Exception: during contract creation, any number of NFTs
-///  may be created and assigned without emitting Transfer. At the time of
-///  any transfer, the approved address for that NFT (if any) is reset to none.
-// fn transfer(from: CanonicalAddr, to: CanonicalAddr, token_id: u32) {
-//     unimplemented!()
-// }
+/// Mints a scratch card for `buyer` and scratches it right away, the outcome is the response's
+/// data. A win that no longer fits in the prize pool turns into a loss, so instant wins can
+/// never add up to more than the pool.
+fn perform_buy_scratch_card<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    buyer: &CanonicalAddr,
+    amount: Uint128,
+    entropy: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
 
-/// @dev This emits when the approved address for an NFT is changed or
-///  reaffirmed. The zero address indicates there is no approved address.
-///  When a Transfer event emits, this also indicates that the approved
-///  address for that NFT (if any) is reset to none.
-// event Approval(address indexed _owner, address indexed _approved, uint256 indexed _tokenId);
+    check_sales_open(&state, env)?;
 
-/// @dev This emits when an operator is enabled or disabled for an owner.
-///  The operator can manage all NFTs of the owner.
-// event ApprovalForAll(address indexed _owner, address indexed _operator, bool _approved);
+    let tiers = match &state.mode {
+        GameMode::ScratchCard { tiers } => tiers.clone(),
+        _ => return Err(ContractError::WrongMode {}.into()),
+    };
 
-/// @notice Count all NFTs assigned to an owner
-/// @dev NFTs assigned to the zero address are considered invalid, and this
-///  function throws for queries about the zero address.
-/// @param _owner An address for whom to query the balance
-/// @return The number of NFTs owned by `_owner`, possibly zero
-fn balance_of<S: Storage, A: Api, Q: Querier>(
-    deps: &Extern<S, A, Q>,
-    owner: &HumanAddr,
-) -> StdResult<u32> {
-    let owner_addr_raw = deps.api.canonical_address(&owner)?;
+    check_price(&state, amount)?;
 
-    if owner_addr_raw == *ZERO_ADDRESS {
-        return Err(throw_gen_err("Can't query the zero address!".to_string()));
+    if state.items.len() as u32 >= MAX_TICKET_COUNT {
+        return Err(ContractError::SoldOut {}.into());
     }
 
-    let state = config_read(&deps.storage).load()?;
-    let mut count = 0;
+    let ticket_id = state.items.len() as u32;
+    let entry = entropy_entry(buyer, ticket_id, env.block.height, &entropy);
+    state.entropy = Binary(mix_entropy(state.entropy.as_slice(), &entry));
 
-    for item in state.items {
-        if item.owner == owner_addr_raw {
-            count = count + 1;
+    let mut roll = (private_random(&state) % ODDS_DENOMINATOR as u64) as u32;
+    let mut value = Uint128(0);
+    for tier in tiers.iter() {
+        if roll < tier.odds {
+            value = tier.amount;
+            break;
+        }
+        roll -= tier.odds;
+    }
+
+    let awarded = checked_add(state.instant_awarded, value)?;
+    if awarded.u128() > state.winning_prize.amount.u128() {
+        value = Uint128(0);
+    } else {
+        state.instant_awarded = awarded;
+    }
+
+    state.deposit = checked_add(state.deposit, amount)?;
+    state.items.push(Ticket {
+        id: ticket_id,
+        owner: buyer.clone(),
+        approved: vec![],
+    });
+    config(&mut deps.storage).save(&state)?;
+
+    sealed(&mut deps.storage).update(|mut sealed_tickets| {
+        sealed_tickets.values.push(value);
+        sealed_tickets.buyers.push(buyer.clone());
+        sealed_tickets.entries.push(Binary(entry));
+        Ok(sealed_tickets)
+    })?;
+
+    let outcome = RevealResponse {
+        ticket_id,
+        winner: value.u128() > 0,
+        value: Coin {
+            denom: state.denom.clone(),
+            amount: value,
+        },
+    };
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("ticket_id", ticket_id)],
+        data: Some(to_binary(&outcome)?),
+    })
+}
+
+fn buy_lotto_ticket<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    numbers: Vec<u8>,
+    entropy: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let amount = single_payment(&env, &state)?;
+    perform_buy_lotto_ticket(deps, &env, &env.message.sender, amount, numbers, entropy)
+}
+
+/// Mints a lotto ticket for `buyer` with the numbers they picked. Its value is only known once
+/// the numbers are drawn by `EndLottery`.
+fn perform_buy_lotto_ticket<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    buyer: &CanonicalAddr,
+    amount: Uint128,
+    mut numbers: Vec<u8>,
+    entropy: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    check_sales_open(&state, env)?;
+
+    let (pick_count, max_number) = match state.mode {
+        GameMode::Lotto {
+            pick_count,
+            max_number,
+            ..
+        } => (pick_count, max_number),
+        _ => return Err(ContractError::WrongMode {}.into()),
+    };
+
+    numbers.sort();
+    numbers.dedup();
+    if numbers.len() != pick_count as usize || numbers.iter().any(|&n| n == 0 || n > max_number) {
+        return Err(ContractError::InvalidPicks {
+            pick_count,
+            max_number,
+        }
+        .into());
+    }
+
+    check_price(&state, amount)?;
+
+    if state.items.len() as u32 >= MAX_TICKET_COUNT {
+        return Err(ContractError::SoldOut {}.into());
+    }
+
+    let ticket_id = state.items.len() as u32;
+    let entry = entropy_entry(buyer, ticket_id, env.block.height, &entropy);
+    state.entropy = Binary(mix_entropy(state.entropy.as_slice(), &entry));
+
+    state.deposit = checked_add(state.deposit, amount)?;
+    state.items.push(Ticket {
+        id: ticket_id,
+        owner: buyer.clone(),
+        approved: vec![],
+    });
+    config(&mut deps.storage).save(&state)?;
+
+    sealed(&mut deps.storage).update(|mut sealed_tickets| {
+        sealed_tickets.values.push(Uint128(0));
+        sealed_tickets.picks.push(numbers);
+        sealed_tickets.paid.push(amount);
+        sealed_tickets.entries.push(Binary(entry));
+        Ok(sealed_tickets)
+    })?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("ticket_id", ticket_id)],
+        data: Some(to_binary(&ticket_id)?),
+    })
+}
+
+fn buy_raffle_ticket<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    entropy: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let amount = single_payment(&env, &state)?;
+    perform_buy_raffle_ticket(deps, &env, &env.message.sender, amount, entropy)
+}
+
+/// Mints a new raffle ticket for `buyer`. There's no supply to sell out besides
+/// `MAX_TICKET_COUNT`, and only minted tickets take part in the draw.
+fn perform_buy_raffle_ticket<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    buyer: &CanonicalAddr,
+    amount: Uint128,
+    entropy: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    check_sales_open(&state, env)?;
+
+    if state.mode != GameMode::Raffle {
+        return Err(ContractError::WrongMode {}.into());
+    }
+
+    check_price(&state, amount)?;
+
+    if state.items.len() as u32 >= MAX_TICKET_COUNT {
+        return Err(ContractError::SoldOut {}.into());
+    }
+
+    let ticket_id = state.items.len() as u32;
+    let entry = entropy_entry(buyer, ticket_id, env.block.height, &entropy);
+    state.entropy = Binary(mix_entropy(state.entropy.as_slice(), &entry));
+
+    state.deposit = checked_add(state.deposit, amount)?;
+    state.items.push(Ticket {
+        id: ticket_id,
+        owner: buyer.clone(),
+        approved: vec![],
+    });
+    config(&mut deps.storage).save(&state)?;
+
+    sealed(&mut deps.storage).update(|mut sealed_tickets| {
+        sealed_tickets.values.push(Uint128(0));
+        sealed_tickets.paid.push(amount);
+        sealed_tickets.entries.push(Binary(entry));
+        Ok(sealed_tickets)
+    })?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("ticket_id", ticket_id)],
+        data: Some(to_binary(&ticket_id)?),
+    })
+}
+
+fn contribute<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    entropy: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let amount = single_payment(&env, &state)?;
+    perform_contribute(deps, &env, &env.message.sender, amount, entropy)
+}
+
+/// Adds `amount` to the weight of the entry held by `contributor`, minting it on their first
+/// contribution. The returned data is the entry's ticket id.
+fn perform_contribute<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    contributor: &CanonicalAddr,
+    amount: Uint128,
+    entropy: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    check_sales_open(&state, env)?;
+
+    if state.mode != GameMode::Weighted {
+        return Err(ContractError::WrongMode {}.into());
+    }
+
+    check_price(&state, amount)?;
+
+    let mut sealed_tickets = sealed_read(&deps.storage).load()?;
+
+    let entry_id = state
+        .items
+        .iter()
+        .find(|item| item.owner == *contributor)
+        .map(|item| item.id);
+    let ticket_id = match entry_id {
+        Some(ticket_id) => ticket_id,
+        None => {
+            if state.items.len() as u32 >= MAX_TICKET_COUNT {
+                return Err(ContractError::SoldOut {}.into());
+            }
+
+            let ticket_id = state.items.len() as u32;
+            state.items.push(Ticket {
+                id: ticket_id,
+                owner: contributor.clone(),
+                approved: vec![],
+            });
+            sealed_tickets.values.push(Uint128(0));
+            sealed_tickets.weights.push(Uint128(0));
+            sealed_tickets.paid.push(Uint128(0));
+            ticket_id
+        }
+    };
+    sealed_tickets.weights[ticket_id as usize] =
+        checked_add(sealed_tickets.weights[ticket_id as usize], amount)?;
+    sealed_tickets.paid[ticket_id as usize] =
+        checked_add(sealed_tickets.paid[ticket_id as usize], amount)?;
+
+    let entry = entropy_entry(contributor, ticket_id, env.block.height, &entropy);
+    state.entropy = Binary(mix_entropy(state.entropy.as_slice(), &entry));
+    sealed_tickets.entries.push(Binary(entry));
+    state.deposit = checked_add(state.deposit, amount)?;
+
+    config(&mut deps.storage).save(&state)?;
+    sealed(&mut deps.storage).save(&sealed_tickets)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![log("ticket_id", ticket_id)],
+        data: Some(to_binary(&ticket_id)?),
+    })
+}
+
+/// Pays the owner of a lootbox its content and burns it. The content is returned as the
+/// response's data.
+fn open_lootbox<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_id: u32,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    match state.mode {
+        GameMode::Lootbox { .. } => {}
+        _ => return Err(ContractError::WrongMode {}.into()),
+    }
+
+    if state.status == LotteryStatus::Settled {
+        return Err(ContractError::LotteryClosed {}.into());
+    }
+
+    if let Some(deadline) = claim_deadline(&state) {
+        if env.block.height >= deadline {
+            return Err(ContractError::ClaimWindowClosed { deadline }.into());
+        }
+    }
+
+    let values = sealed_read(&deps.storage).load()?.values;
+    let (amount, offers) = redeem_tickets(
+        &deps.api,
+        &mut state,
+        &values,
+        &env.message.sender,
+        &[ticket_id],
+    )?;
+    state.deposit = checked_sub(state.deposit, amount)?;
+    config(&mut deps.storage).save(&state)?;
+
+    let content = Coin {
+        denom: state.denom.clone(),
+        amount,
+    };
+
+    let mut messages = offer_refunds(deps, &env, &state, &offers)?;
+    if amount.u128() > 0 {
+        let contract_addr = deps.api.human_address(&env.contract.address)?;
+        let to = deps.api.human_address(&env.message.sender)?;
+        messages.push(payout_msg(deps, &state, &contract_addr, to, amount)?);
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: Some(to_binary(&content)?),
+    })
+}
+
+/// SNIP-20 `Send` callback, only accepted from the lottery's token contract
+fn receive<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    from: &HumanAddr,
+    amount: Uint128,
+    msg: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+
+    if state.token.as_ref() != Some(&env.message.sender) {
+        return Err(ContractError::Unauthorized {}.into());
+    }
+
+    let msg: ReceiveMsg = match msg {
+        Some(msg) => from_binary(&msg)?,
+        None => return Err(ContractError::MissingReceiveMsg {}.into()),
+    };
+    let from_addr_raw = deps.api.canonical_address(from)?;
+
+    match msg {
+        ReceiveMsg::BuyTicket { ticket_id, entropy } => {
+            check_funded(&state)?;
+
+            perform_purchase(deps, &env, &from_addr_raw, amount, ticket_id, entropy)
+        }
+        ReceiveMsg::BuyLootbox { entropy } => {
+            check_funded(&state)?;
+
+            perform_buy_lootbox(deps, &env, &from_addr_raw, amount, entropy)
+        }
+        ReceiveMsg::BuyScratchCard { entropy } => {
+            check_funded(&state)?;
+
+            perform_buy_scratch_card(deps, &env, &from_addr_raw, amount, entropy)
+        }
+        ReceiveMsg::BuyLottoTicket { numbers, entropy } => {
+            check_funded(&state)?;
+
+            perform_buy_lotto_ticket(deps, &env, &from_addr_raw, amount, numbers, entropy)
+        }
+        ReceiveMsg::BuyRaffleTicket { entropy } => {
+            check_funded(&state)?;
+
+            perform_buy_raffle_ticket(deps, &env, &from_addr_raw, amount, entropy)
+        }
+        ReceiveMsg::Contribute { entropy } => {
+            check_funded(&state)?;
+
+            perform_contribute(deps, &env, &from_addr_raw, amount, entropy)
+        }
+        ReceiveMsg::BuyListed { ticket_id } => {
+            perform_buy_listed(deps, &env, &from_addr_raw, amount, ticket_id)
+        }
+        ReceiveMsg::MakeOffer { ticket_id } => {
+            perform_make_offer(deps, &env, &from_addr_raw, amount, ticket_id)
+        }
+        ReceiveMsg::FundPrize {} => {
+            if from_addr_raw != state.contract_owner {
+                return Err(ContractError::Unauthorized {}.into());
+            }
+
+            if state.status != LotteryStatus::Open {
+                return Err(ContractError::LotteryClosed {}.into());
+            }
+
+            config(&mut deps.storage).update(|mut state| {
+                state.deposit = checked_add(state.deposit, amount)?;
+                if state.deposit.u128() >= state.winning_prize.amount.u128() {
+                    state.prize_funded = true;
+                }
+                Ok(state)
+            })?;
+
+            Ok(HandleResponse::default())
+        }
+    }
+}
+
+/// Puts an owned ticket up for sale at `price`, replacing any previous listing
+fn list_ticket<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_id: u32,
+    price: Uint128,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    check_sales_open(&state, &env)?;
+
+    check_not_frozen(&state, &env)?;
+
+    if !is_token_id_valid(ticket_id, &state) {
+        return Err(ContractError::InvalidTicket { ticket_id }.into());
+    }
+
+    if state.items[ticket_id as usize].owner != env.message.sender {
+        return Err(ContractError::NotOwner {
+            address: deps.api.human_address(&env.message.sender)?,
+            ticket_id,
+        }
+        .into());
+    }
+
+    if price.u128() == 0 {
+        return Err(ContractError::ZeroPrice {}.into());
+    }
+
+    state
+        .listings
+        .retain(|listing| listing.ticket_id != ticket_id);
+    state.listings.push(Listing {
+        ticket_id,
+        seller: env.message.sender.clone(),
+        price,
+    });
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn delist<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_id: u32,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    let position = state
+        .listings
+        .iter()
+        .position(|listing| listing.ticket_id == ticket_id);
+
+    match position {
+        Some(i) if state.listings[i].seller == env.message.sender => {
+            state.listings.remove(i);
+        }
+        Some(_) => return Err(ContractError::Unauthorized {}.into()),
+        None => return Err(ContractError::NotListed { ticket_id }.into()),
+    }
+
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse::default())
+}
+
+fn buy_listed<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_id: u32,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let amount = single_payment(&env, &state)?;
+    perform_buy_listed(deps, &env, &env.message.sender, amount, ticket_id)
+}
+
+/// Transfers a listed ticket to `buyer` and pays the seller out of the `amount` the buyer sent,
+/// all in the same transaction
+fn perform_buy_listed<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    buyer: &CanonicalAddr,
+    amount: Uint128,
+    ticket_id: u32,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+
+    check_sales_open(&state, env)?;
+
+    check_not_frozen(&state, env)?;
+
+    let listing = match state
+        .listings
+        .iter()
+        .find(|listing| listing.ticket_id == ticket_id)
+    {
+        Some(listing) => listing.clone(),
+        None => return Err(ContractError::NotListed { ticket_id }.into()),
+    };
+
+    // Never sell a ticket the seller doesn't hold anymore
+    if state.items[ticket_id as usize].owner != listing.seller {
+        return Err(ContractError::NotListed { ticket_id }.into());
+    }
+
+    if amount != listing.price {
+        return Err(ContractError::WrongPrice {
+            price: listing.price,
+            sent: amount,
+        }
+        .into());
+    }
+
+    // Also clears the listing
+    perform_transfer(deps, buyer, ticket_id)?;
+
+    Ok(HandleResponse {
+        messages: pay_sale(deps, env, &listing.seller, listing.price)?,
+        log: vec![],
+        data: None,
+    })
+}
+
+fn make_offer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_id: u32,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let amount = single_payment(&env, &state)?;
+    perform_make_offer(deps, &env, &env.message.sender, amount, ticket_id)
+}
+
+/// Escrows `amount` as the offer on a ticket. There is a single offer per ticket, so a new
+/// one has to be higher and the outbid bidder is refunded.
+fn perform_make_offer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    bidder: &CanonicalAddr,
+    amount: Uint128,
+    ticket_id: u32,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    if state.status != LotteryStatus::Open {
+        return Err(ContractError::LotteryClosed {}.into());
+    }
+
+    if !is_token_id_valid(ticket_id, &state) {
+        return Err(ContractError::InvalidTicket { ticket_id }.into());
+    }
+
+    if !is_sold(&state.items[ticket_id as usize], &env.contract.address) {
+        return Err(ContractError::NotOnSecondaryMarket { ticket_id }.into());
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let position = state
+        .offers
+        .iter()
+        .position(|offer| offer.ticket_id == ticket_id);
+
+    if let Some(i) = position {
+        let outbid = state.offers.remove(i);
+        if amount.u128() <= outbid.amount.u128() {
+            return Err(ContractError::OfferTooLow {
+                current: outbid.amount,
+                sent: amount,
+            }
+            .into());
+        }
+
+        let contract_addr = deps.api.human_address(&env.contract.address)?;
+        let to = deps.api.human_address(&outbid.bidder)?;
+        messages.push(payout_msg(deps, &state, &contract_addr, to, outbid.amount)?);
+    } else if amount.u128() == 0 {
+        return Err(ContractError::ZeroPrice {}.into());
+    }
+
+    state.offers.push(Offer {
+        ticket_id,
+        bidder: bidder.clone(),
+        amount,
+    });
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: None,
+    })
+}
+
+/// Removes the sender's offer on a ticket and refunds it
+fn cancel_offer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_id: u32,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    let position = state
+        .offers
+        .iter()
+        .position(|offer| offer.ticket_id == ticket_id);
+
+    let offer = match position {
+        Some(i) if state.offers[i].bidder == env.message.sender => state.offers.remove(i),
+        Some(_) => return Err(ContractError::Unauthorized {}.into()),
+        None => return Err(ContractError::NoOffer { ticket_id }.into()),
+    };
+    config(&mut deps.storage).save(&state)?;
+
+    let contract_addr = deps.api.human_address(&env.contract.address)?;
+    let to = deps.api.human_address(&offer.bidder)?;
+
+    Ok(HandleResponse {
+        messages: vec![payout_msg(deps, &state, &contract_addr, to, offer.amount)?],
+        log: vec![],
+        data: None,
+    })
+}
+
+/// Sells the sender's ticket to the bidder, paying the sender out of the escrowed offer
+fn accept_offer<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_id: u32,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    check_sales_open(&state, &env)?;
+
+    check_not_frozen(&state, &env)?;
+
+    if !is_token_id_valid(ticket_id, &state) {
+        return Err(ContractError::InvalidTicket { ticket_id }.into());
+    }
+
+    if state.items[ticket_id as usize].owner != env.message.sender {
+        return Err(ContractError::NotOwner {
+            address: deps.api.human_address(&env.message.sender)?,
+            ticket_id,
+        }
+        .into());
+    }
+
+    let position = state
+        .offers
+        .iter()
+        .position(|offer| offer.ticket_id == ticket_id);
+
+    let offer = match position {
+        Some(i) => state.offers.remove(i),
+        None => return Err(ContractError::NoOffer { ticket_id }.into()),
+    };
+    config(&mut deps.storage).save(&state)?;
+
+    perform_transfer(deps, &offer.bidder, ticket_id)?;
+
+    Ok(HandleResponse {
+        messages: pay_sale(deps, &env, &env.message.sender, offer.amount)?,
+        log: vec![],
+        data: None,
+    })
+}
+
+/// Pays the seller of a secondary sale its price minus the royalty. The royalty goes to the
+/// beneficiary if there is one, otherwise it's added to the deposit and so to the host's payout.
+fn pay_sale<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    seller: &CanonicalAddr,
+    price: Uint128,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    let royalty = match price.u128().checked_mul(state.royalty_bps as u128) {
+        Some(amount) => Uint128(amount / 10_000),
+        None => return Err(ContractError::Overflow {}.into()),
+    };
+    let proceeds = checked_sub(price, royalty)?;
+
+    let contract_addr = deps.api.human_address(&env.contract.address)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+
+    if royalty.u128() > 0 {
+        match &state.royalty_recipient {
+            Some(recipient) => {
+                let to = deps.api.human_address(recipient)?;
+                messages.push(payout_msg(deps, &state, &contract_addr, to, royalty)?);
+            }
+            None => {
+                state.deposit = checked_add(state.deposit, royalty)?;
+                config(&mut deps.storage).save(&state)?;
+            }
+        }
+    }
+
+    if proceeds.u128() > 0 {
+        let to = deps.api.human_address(seller)?;
+        messages.push(payout_msg(deps, &state, &contract_addr, to, proceeds)?);
+    }
+
+    Ok(messages)
+}
+
+/// Builds the message paying `amount` of the lottery's denom to `to`, either as a bank send
+/// or as a SNIP-20 transfer
+fn payout_msg<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    state: &State,
+    contract_addr: &HumanAddr,
+    to: HumanAddr,
+    amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    match &state.token {
+        Some(token) => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.human_address(token)?,
+            msg: to_binary(&Snip20Msg::Transfer {
+                recipient: to,
+                amount,
+            })?,
+            send: vec![],
+        })),
+        None => Ok(CosmosMsg::Bank(BankMsg::Send {
+            from_address: contract_addr.clone(),
+            to_address: to,
+            amount: vec![Coin {
+                denom: state.denom.clone(),
+                amount,
+            }],
+        })),
+    }
+}
+
+/// Fixes the outcome of the lottery. Sold tickets keep their value until their owners claim
+/// it with `ClaimPrize`, everything else in the deposit is left for the host to claim.
+fn end_lottery<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    reveal: Option<Binary>,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    if state.status != LotteryStatus::Open {
+        return Err(ContractError::LotteryClosed {}.into());
+    }
+
+    // Anyone can draw from `draw_at` on, without it only the host decides when sales end
+    match state.draw_at {
+        Some(draw_at) => {
+            if env.block.height < draw_at {
+                return Err(ContractError::TooEarly { draw_at }.into());
+            }
+        }
+        None => {
+            if env.message.sender != state.contract_owner {
+                return Err(ContractError::Unauthorized {}.into());
+            }
+        }
+    }
+
+    let mut sealed_tickets = sealed_read(&deps.storage).load()?;
+
+    // Not enough tickets sold, cancel the draw and refund everyone
+    let sold = state
+        .items
+        .iter()
+        .filter(|item| is_sold(item, &state.contract_addr))
+        .count() as u32;
+    if sold < state.min_tickets_sold {
+        return start_refunds(deps, &env, state, &sealed_tickets, "too_few_tickets");
+    }
+
+    // The host didn't reveal the seed in time, nobody can be drawn anymore
+    if let Some(draw_at) = state.draw_at {
+        if state.seed_commitment.is_some()
+            && draws_from_reveal(&state.mode)
+            && env.block.height >= draw_at.saturating_add(state.reveal_window)
+        {
+            return start_refunds(deps, &env, state, &sealed_tickets, "seed_not_revealed");
+        }
+    }
+
+    let mut winners = vec![];
+    let mut numbers = vec![];
+    let mut weights: Vec<Uint128> = vec![];
+
+    // Pick the golden ticket from the revealed seed and the purchases' entropy
+    let seed = match state.seed_commitment.clone() {
+        Some(commitment) if state.mode == GameMode::Classic => {
+            let reveal = check_reveal(&commitment, reveal)?;
+
+            let ticket_count = state.items.len() as u32;
+            let golden = draw_ticket(reveal.as_slice(), state.entropy.as_slice(), ticket_count);
+            sealed_tickets.values[golden as usize] = state.winning_prize.amount;
+            sealed_tickets.golden = Some(golden);
+            Some(reveal)
+        }
+        // Draw the lotto numbers the same way, and split each tier among its winners
+        Some(commitment) => match state.mode.clone() {
+            GameMode::Lotto {
+                pick_count,
+                max_number,
+                tiers,
+            } => {
+                let reveal = check_reveal(&commitment, reveal)?;
+
+                numbers = draw_numbers(
+                    reveal.as_slice(),
+                    state.entropy.as_slice(),
+                    pick_count,
+                    max_number,
+                );
+                winners = award_lotto_tiers(&state, &mut sealed_tickets, &numbers, &tiers)?;
+                Some(reveal)
+            }
+            // Only sold tickets take part in a raffle, so there's a winner whenever one sold
+            GameMode::Raffle => {
+                let reveal = check_reveal(&commitment, reveal)?;
+
+                let sold: Vec<u32> = state
+                    .items
+                    .iter()
+                    .filter(|item| is_sold(item, &state.contract_addr))
+                    .map(|item| item.id)
+                    .collect();
+                if !sold.is_empty() {
+                    let pick = draw_ticket(
+                        reveal.as_slice(),
+                        state.entropy.as_slice(),
+                        sold.len() as u32,
+                    );
+                    let golden = sold[pick as usize];
+                    sealed_tickets.values[golden as usize] = state.winning_prize.amount;
+                    sealed_tickets.golden = Some(golden);
+                }
+                Some(reveal)
+            }
+            // Each entry wins with odds proportional to its weight
+            GameMode::Weighted => {
+                let reveal = check_reveal(&commitment, reveal)?;
+
+                weights = state
+                    .items
+                    .iter()
+                    .map(|item| {
+                        if is_sold(item, &state.contract_addr) {
+                            sealed_tickets.weights[item.id as usize]
+                        } else {
+                            Uint128(0)
+                        }
+                    })
+                    .collect();
+                if let Some(golden) =
+                    draw_weighted(reveal.as_slice(), state.entropy.as_slice(), &weights)
+                {
+                    sealed_tickets.values[golden as usize] = state.winning_prize.amount;
+                    sealed_tickets.golden = Some(golden);
+                }
+                Some(reveal)
+            }
+            // Scratch cards were decided by the private seed, publish it so anyone can check
+            // them
+            GameMode::ScratchCard { .. } => Some(state.prng_seed.clone()),
+            _ => None,
+        },
+        None => None,
+    };
+
+    if state.consolation_bps > 0 {
+        award_consolation(&state, &mut sealed_tickets)?;
+    }
+
+    // Total payouts must never exceed what the contract holds
+    state.host_remainder = checked_sub(
+        state.deposit,
+        owed_to_tickets(&state, &sealed_tickets.values)?,
+    )?;
+    sealed(&mut deps.storage).save(&sealed_tickets)?;
+
+    // Beneficiaries are paid their share right away
+    let beneficiary_payouts = beneficiary_shares(&state, state.host_remainder)?;
+    let (mut messages, logs) = pay_beneficiaries(deps, &env, &mut state, &beneficiary_payouts)?;
+
+    // Record the outcome before tickets get burned by claims
+    if let GameMode::ScratchCard { tiers } = &state.mode {
+        for item in state.items.iter() {
+            let value = sealed_tickets.values[item.id as usize];
+            if value.u128() == 0 {
+                continue;
+            }
+
+            winners.push(WinningTicket {
+                ticket_id: item.id,
+                tier: tiers
+                    .iter()
+                    .position(|tier| tier.amount == value)
+                    .unwrap_or(0) as u32,
+                // Cards claimed before the draw are burned already
+                owner: if item.owner == *ZERO_ADDRESS {
+                    Some(sealed_tickets.buyers[item.id as usize].clone())
+                } else {
+                    Some(item.owner.clone())
+                },
+                payout: Coin {
+                    denom: state.denom.clone(),
+                    amount: value,
+                },
+            });
+        }
+    }
+    if let Some(golden) = sealed_tickets.golden {
+        let item = &state.items[golden as usize];
+        winners.push(WinningTicket {
+            ticket_id: golden,
+            tier: 0,
+            owner: if is_sold(item, &state.contract_addr) {
+                Some(item.owner.clone())
+            } else {
+                None
+            },
+            payout: state.winning_prize.clone(),
+        });
+    }
+    let result = DrawResult {
+        winners,
+        host_remainder: state.host_remainder,
+        drawn_at: env.block.height,
+        entropy: state
+            .seed_commitment
+            .as_ref()
+            .map(|_| state.entropy.clone()),
+        // Purchases can be replayed once the seed is public
+        entries: if seed.is_some() {
+            sealed_tickets.entries.clone()
+        } else {
+            vec![]
+        },
+        seed,
+        numbers,
+        weights,
+        beneficiary_payouts,
+    };
+    draw_result(&mut deps.storage).save(&result)?;
+
+    // Trading is over, listed tickets can't be bought anymore
+    messages.extend(refund_offers(deps, &env, &mut state)?);
+    state.listings = vec![];
+
+    // Mark lottery as drawn and save state
+    state.status = LotteryStatus::Drawn;
+    state.drawn_at = Some(env.block.height);
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: logs,
+        data: None,
+    })
+}
+
+/// Cancels the draw. Buyers get their payment back with `ClaimRefund` and the host recovers the
+/// rest of the deposit with `ClaimRemainder`.
+fn start_refunds<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: &Env,
+    mut state: State,
+    sealed_tickets: &SealedTickets,
+    reason: &str,
+) -> StdResult<HandleResponse> {
+    state.host_remainder = checked_sub(
+        state.deposit,
+        owed_to_tickets(&state, &sealed_tickets.paid)?,
+    )?;
+    let messages = refund_offers(deps, env, &mut state)?;
+    state.listings = vec![];
+    state.status = LotteryStatus::Refunding;
+    // Refunds expire like prizes do
+    state.drawn_at = Some(env.block.height);
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![log("refunding", reason)],
+        data: None,
+    })
+}
+
+/// Trading is over, gives the bidders their funds back
+fn refund_offers<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+    state: &mut State,
+) -> StdResult<Vec<CosmosMsg>> {
+    let messages = offer_refunds(deps, env, state, &state.offers)?;
+    state.offers = vec![];
+
+    Ok(messages)
+}
+
+/// Builds the messages giving the bidders of `offers` their funds back
+fn offer_refunds<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+    state: &State,
+    offers: &[Offer],
+) -> StdResult<Vec<CosmosMsg>> {
+    let contract_addr = deps.api.human_address(&env.contract.address)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for offer in offers {
+        let bidder = deps.api.human_address(&offer.bidder)?;
+        messages.push(payout_msg(
+            deps,
+            state,
+            &contract_addr,
+            bidder,
+            offer.amount,
+        )?);
+    }
+
+    Ok(messages)
+}
+
+/// Share of the host's `remainder` each beneficiary is owed
+fn beneficiary_shares(state: &State, remainder: Uint128) -> StdResult<Vec<BeneficiaryPayout>> {
+    let mut payouts = vec![];
+    for beneficiary in state.beneficiaries.iter() {
+        let amount = remainder
+            .u128()
+            .checked_mul(beneficiary.bps as u128)
+            .ok_or(ContractError::Overflow {})?
+            / 10_000;
+        if amount == 0 {
+            continue;
+        }
+
+        payouts.push(BeneficiaryPayout {
+            address: beneficiary.address.clone(),
+            amount: Uint128(amount),
+        });
+    }
+
+    Ok(payouts)
+}
+
+/// Pays the beneficiaries their share out of the host's remainder, logging every payout
+fn pay_beneficiaries<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    env: &Env,
+    state: &mut State,
+    payouts: &[BeneficiaryPayout],
+) -> StdResult<(Vec<CosmosMsg>, Vec<LogAttribute>)> {
+    let contract_addr = deps.api.human_address(&env.contract.address)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut logs = vec![];
+    for payout in payouts {
+        state.host_remainder = checked_sub(state.host_remainder, payout.amount)?;
+        state.deposit = checked_sub(state.deposit, payout.amount)?;
+
+        let to = deps.api.human_address(&payout.address)?;
+        logs.push(log("beneficiary", format!("{}:{}", to, payout.amount)));
+        messages.push(payout_msg(deps, state, &contract_addr, to, payout.amount)?);
+    }
+
+    Ok((messages, logs))
+}
+
+/// The revealed seed, which has to match the commitment
+fn check_reveal(commitment: &Binary, reveal: Option<Binary>) -> StdResult<Binary> {
+    let reveal = match reveal {
+        Some(reveal) => reveal,
+        None => return Err(ContractError::InvalidReveal {}.into()),
+    };
+    if !seed_matches(commitment.as_slice(), reveal.as_slice()) {
+        return Err(ContractError::InvalidReveal {}.into());
+    }
+
+    Ok(reveal)
+}
+
+/// Sets the value of every lotto ticket from its matches with the drawn numbers. Each tier's
+/// share of the pool is split evenly among its winners, whatever isn't won stays with the host.
+fn award_lotto_tiers(
+    state: &State,
+    sealed_tickets: &mut SealedTickets,
+    numbers: &[u8],
+    tiers: &[LottoTier],
+) -> StdResult<Vec<WinningTicket>> {
+    let mut tier_winners: Vec<Vec<u32>> = vec![vec![]; tiers.len()];
+    for item in state.items.iter() {
+        if !is_sold(item, &state.contract_addr) {
+            continue;
+        }
+
+        let matches = sealed_tickets.picks[item.id as usize]
+            .iter()
+            .filter(|n| numbers.contains(*n))
+            .count() as u8;
+        if let Some(tier) = tiers.iter().position(|tier| tier.matches == matches) {
+            tier_winners[tier].push(item.id);
+        }
+    }
+
+    let mut winners = vec![];
+    for (tier, ticket_ids) in tier_winners.iter().enumerate() {
+        if ticket_ids.is_empty() {
+            continue;
+        }
+
+        let share = state
+            .winning_prize
+            .amount
+            .u128()
+            .checked_mul(tiers[tier].share_bps as u128)
+            .ok_or(ContractError::Overflow {})?
+            / 10_000;
+        let payout = Uint128(share / ticket_ids.len() as u128);
+
+        for &ticket_id in ticket_ids {
+            sealed_tickets.values[ticket_id as usize] = payout;
+            winners.push(WinningTicket {
+                ticket_id,
+                tier: tier as u32,
+                owner: Some(state.items[ticket_id as usize].owner.clone()),
+                payout: Coin {
+                    denom: state.denom.clone(),
+                    amount: payout,
+                },
+            });
+        }
+    }
+
+    Ok(winners)
+}
+
+/// Splits the consolation share of the ticket revenue evenly among the sold tickets that
+/// didn't win anything. The rounding dust is left to the host.
+fn award_consolation(state: &State, sealed_tickets: &mut SealedTickets) -> StdResult<()> {
+    let mut revenue = Uint128(0);
+    let mut losers = vec![];
+    for item in state.items.iter() {
+        if !is_sold(item, &state.contract_addr) {
+            continue;
+        }
+
+        revenue = checked_add(revenue, sealed_tickets.paid[item.id as usize])?;
+        if sealed_tickets.values[item.id as usize].u128() == 0 {
+            losers.push(item.id);
+        }
+    }
+
+    if losers.is_empty() {
+        return Ok(());
+    }
+
+    let pool = revenue
+        .u128()
+        .checked_mul(state.consolation_bps as u128)
+        .ok_or(ContractError::Overflow {})?
+        / 10_000;
+    let consolation = Uint128(pool / losers.len() as u128);
+    for ticket_id in losers {
+        sealed_tickets.values[ticket_id as usize] = consolation;
+    }
+
+    Ok(())
+}
+
+/// Hash of a purchase, mixed into the entropy of the draw. The buyer's secret `entropy` keeps
+/// anyone, the host included, from working out the draw from the public purchases. Entries stay
+/// sealed until the draw publishes them in `DrawResult`.
+pub fn entropy_entry(
+    buyer: &CanonicalAddr,
+    ticket_id: u32,
+    height: u64,
+    entropy: &Option<Binary>,
+) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(buyer.as_slice());
+    hasher.update(&ticket_id.to_be_bytes());
+    hasher.update(&height.to_be_bytes());
+    if let Some(entropy) = entropy {
+        hasher.update(entropy.as_slice());
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Next link of the entropy chain, which starts at the seed commitment
+pub fn mix_entropy(entropy: &[u8], entry: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(entropy);
+    hasher.update(entry);
+    hasher.finalize().to_vec()
+}
+
+/// The commitment is the SHA-256 hash of the seed the host reveals at the draw
+pub fn seed_matches(commitment: &[u8], reveal: &[u8]) -> bool {
+    Sha256::digest(reveal).as_slice() == commitment
+}
+
+/// Picks the golden ticket out of `ticket_count` from the revealed seed and the final entropy.
+/// This is the draw `verify_draw` reproduces offline.
+pub fn draw_ticket(reveal: &[u8], entropy: &[u8], ticket_count: u32) -> u32 {
+    (hash_to_u64(reveal, entropy) % ticket_count as u64) as u32
+}
+
+/// Draws `pick_count` different numbers from 1 to `max_number` out of the revealed seed and the
+/// final entropy, sorted
+pub fn draw_numbers(reveal: &[u8], entropy: &[u8], pick_count: u8, max_number: u8) -> Vec<u8> {
+    let mut numbers = vec![];
+    let mut round: u32 = 0;
+    while numbers.len() < pick_count as usize {
+        let entry = mix_entropy(entropy, &round.to_be_bytes());
+        let number = (hash_to_u64(reveal, &entry) % max_number as u64) as u8 + 1;
+        if !numbers.contains(&number) {
+            numbers.push(number);
+        }
+        round += 1;
+    }
+
+    numbers.sort();
+    numbers
+}
+
+/// Picks an entry with odds proportional to its weight, from the revealed seed and the final
+/// entropy. The roll spans 128 bits so that every entry can win whatever the total weight.
+/// Nobody wins if every weight is zero.
+pub fn draw_weighted(reveal: &[u8], entropy: &[u8], weights: &[Uint128]) -> Option<u32> {
+    let total: u128 = weights.iter().map(|weight| weight.u128()).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut roll = hash_to_u128(reveal, entropy) % total;
+    for (i, weight) in weights.iter().enumerate() {
+        if roll < weight.u128() {
+            return Some(i as u32);
+        }
+        roll -= weight.u128();
+    }
+
+    None
+}
+
+/// Random number from the contract's private seed and the current entropy. The seed never
+/// leaves the contract, so unlike the committed draw nobody can predict it.
+fn private_random(state: &State) -> u64 {
+    hash_to_u64(state.prng_seed.as_slice(), state.entropy.as_slice())
+}
+
+fn hash_to_u64(seed: &[u8], entropy: &[u8]) -> u64 {
+    (hash_to_u128(seed, entropy) >> 64) as u64
+}
+
+fn hash_to_u128(seed: &[u8], entropy: &[u8]) -> u128 {
+    let mut hasher = Sha256::new();
+    hasher.update(seed);
+    hasher.update(entropy);
+    let hash = hasher.finalize();
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    u128::from_be_bytes(bytes)
+}
+
+/// Sum of the values of every sold ticket, which is what the draw owes to ticket holders
+fn owed_to_tickets(state: &State, values: &[Uint128]) -> StdResult<Uint128> {
+    let mut owed = Uint128(0);
+    for item in state.items.iter() {
+        if is_sold(item, &state.contract_addr) {
+            owed = checked_add(owed, values[item.id as usize])?;
+        }
+    }
+
+    Ok(owed)
+}
+
+/// Pays the sender the value of the given tickets and burns them
+fn claim_prize<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_ids: Vec<u32>,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    // Scratch cards are won at purchase, so they can be claimed before the draw
+    let instant = match state.mode {
+        GameMode::ScratchCard { .. } => state.status == LotteryStatus::Open,
+        _ => false,
+    };
+    if state.status != LotteryStatus::Drawn && !instant {
+        return Err(ContractError::NotDrawn {}.into());
+    }
+
+    if let Some(deadline) = claim_deadline(&state) {
+        if env.block.height >= deadline {
+            return Err(ContractError::ClaimWindowClosed { deadline }.into());
+        }
+    }
+
+    let values = sealed_read(&deps.storage).load()?.values;
+    let (amount, offers) = redeem_tickets(
+        &deps.api,
+        &mut state,
+        &values,
+        &env.message.sender,
+        &ticket_ids,
+    )?;
+    state.deposit = checked_sub(state.deposit, amount)?;
+    config(&mut deps.storage).save(&state)?;
+
+    let mut messages = offer_refunds(deps, &env, &state, &offers)?;
+    if amount.u128() > 0 {
+        let contract_addr = deps.api.human_address(&env.contract.address)?;
+        let to = deps.api.human_address(&env.message.sender)?;
+        messages.push(payout_msg(deps, &state, &contract_addr, to, amount)?);
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: None,
+    })
+}
+
+/// Pays the sender back what was paid for the given tickets and burns them
+fn claim_refund<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_ids: Vec<u32>,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    if state.status != LotteryStatus::Refunding {
+        return Err(ContractError::NotRefunding {}.into());
+    }
+
+    if let Some(deadline) = claim_deadline(&state) {
+        if env.block.height >= deadline {
+            return Err(ContractError::ClaimWindowClosed { deadline }.into());
+        }
+    }
+
+    let paid = sealed_read(&deps.storage).load()?.paid;
+    let (amount, offers) = redeem_tickets(
+        &deps.api,
+        &mut state,
+        &paid,
+        &env.message.sender,
+        &ticket_ids,
+    )?;
+    state.deposit = checked_sub(state.deposit, amount)?;
+    config(&mut deps.storage).save(&state)?;
+
+    let mut messages = offer_refunds(deps, &env, &state, &offers)?;
+    if amount.u128() > 0 {
+        let contract_addr = deps.api.human_address(&env.contract.address)?;
+        let to = deps.api.human_address(&env.message.sender)?;
+        messages.push(payout_msg(deps, &state, &contract_addr, to, amount)?);
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: None,
+    })
+}
+
+/// Burns tickets held by `owner` and returns their total value, along with the offers on them,
+/// which have to be refunded
+fn redeem_tickets<A: Api>(
+    api: &A,
+    state: &mut State,
+    values: &[Uint128],
+    owner: &CanonicalAddr,
+    ticket_ids: &[u32],
+) -> StdResult<(Uint128, Vec<Offer>)> {
+    let mut amount = Uint128(0);
+    for &ticket_id in ticket_ids {
+        if !is_token_id_valid(ticket_id, state) {
+            return Err(ContractError::InvalidTicket { ticket_id }.into());
+        }
+
+        let item = &mut state.items[ticket_id as usize];
+
+        if item.owner == *ZERO_ADDRESS {
+            return Err(ContractError::AlreadyRedeemed { ticket_id }.into());
+        }
+
+        if item.owner != *owner {
+            return Err(ContractError::NotOwner {
+                address: api.human_address(owner)?,
+                ticket_id,
+            }
+            .into());
+        }
+
+        amount = checked_add(amount, values[ticket_id as usize])?;
+        item.owner = (*ZERO_ADDRESS).clone();
+    }
+
+    // A burned ticket can't be traded anymore
+    state
+        .listings
+        .retain(|listing| !ticket_ids.contains(&listing.ticket_id));
+    let (offers, kept): (Vec<Offer>, Vec<Offer>) = state
+        .offers
+        .drain(..)
+        .partition(|offer| ticket_ids.contains(&offer.ticket_id));
+    state.offers = kept;
+
+    Ok((amount, offers))
+}
+
+/// Pays the host whatever was left in the deposit after the draw
+fn claim_remainder<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    if env.message.sender != state.contract_owner {
+        return Err(ContractError::Unauthorized {}.into());
+    }
+
+    // The host recovers the prize of a cancelled lottery the same way
+    if state.status != LotteryStatus::Drawn && state.status != LotteryStatus::Refunding {
+        return Err(ContractError::NotDrawn {}.into());
+    }
+
+    let amount = state.host_remainder;
+    if amount.u128() == 0 {
+        return Err(ContractError::NothingToClaim {}.into());
+    }
+
+    state.deposit = checked_sub(state.deposit, amount)?;
+    state.host_remainder = Uint128(0);
+    config(&mut deps.storage).save(&state)?;
+
+    let contract_addr = deps.api.human_address(&env.contract.address)?;
+    let owner_addr = deps.api.human_address(&state.contract_owner)?;
+
+    Ok(HandleResponse {
+        messages: vec![payout_msg(
+            deps,
+            &state,
+            &contract_addr,
+            owner_addr,
+            amount,
+        )?],
+        log: vec![],
+        data: None,
+    })
+}
+
+/// Last block (exclusive) at which prizes can be claimed, if claims expire at all
+fn claim_deadline(state: &State) -> Option<u64> {
+    match (state.drawn_at, state.claim_window) {
+        (Some(drawn_at), Some(window)) => Some(drawn_at.saturating_add(window)),
+        _ => None,
+    }
+}
+
+/// Once the claim window is over, sends everything still in the deposit to the treasury, or
+/// to the host if there is none, and burns the unclaimed tickets. Unclaimed refunds of a
+/// cancelled lottery are swept the same way.
+fn sweep_unclaimed<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    if env.message.sender != state.contract_owner
+        && state.treasury.as_ref() != Some(&env.message.sender)
+    {
+        return Err(ContractError::Unauthorized {}.into());
+    }
+
+    if state.status != LotteryStatus::Drawn && state.status != LotteryStatus::Refunding {
+        return Err(ContractError::NotDrawn {}.into());
+    }
+
+    let deadline = match claim_deadline(&state) {
+        Some(deadline) => deadline,
+        None => return Err(ContractError::NoClaimWindow {}.into()),
+    };
+    if env.block.height < deadline {
+        return Err(ContractError::ClaimWindowOpen { deadline }.into());
+    }
+
+    for item in state.items.iter_mut() {
+        if is_sold(item, &env.contract.address) {
+            item.owner = (*ZERO_ADDRESS).clone();
+        }
+    }
+
+    let amount = state.deposit;
+    let recipient = state
+        .treasury
+        .clone()
+        .unwrap_or_else(|| state.contract_owner.clone());
+
+    state.deposit = Uint128(0);
+    state.host_remainder = Uint128(0);
+    state.status = LotteryStatus::Settled;
+    config(&mut deps.storage).save(&state)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if amount.u128() > 0 {
+        let contract_addr = deps.api.human_address(&env.contract.address)?;
+        let to = deps.api.human_address(&recipient)?;
+        messages.push(payout_msg(deps, &state, &contract_addr, to, amount)?);
+    }
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: None,
+    })
+}
+
+/// Pushes the payouts of up to `limit` tickets, starting where the previous batch stopped.
+/// Once every ticket was processed the host gets the remainder and the lottery is settled.
+fn settle_batch<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    limit: u32,
+) -> StdResult<HandleResponse> {
+    let mut state = config(&mut deps.storage).load()?;
+
+    if env.message.sender != state.contract_owner {
+        return Err(ContractError::Unauthorized {}.into());
+    }
+
+    if state.status != LotteryStatus::Drawn {
+        return Err(ContractError::NotDrawn {}.into());
+    }
+
+    let limit = limit.max(1).min(MAX_SETTLE_BATCH);
+    let start = state.settle_cursor as usize;
+    let end = (start + limit as usize).min(state.items.len());
+
+    let values = sealed_read(&deps.storage).load()?.values;
+    let mut payouts: Vec<(CanonicalAddr, Uint128)> = vec![];
+    for item in state.items[start..end].iter_mut() {
+        if !is_sold(item, &env.contract.address) {
+            continue;
+        }
+
+        add_payout(&mut payouts, &item.owner, values[item.id as usize])?;
+        item.owner = (*ZERO_ADDRESS).clone();
+    }
+    state.settle_cursor = end as u32;
+
+    // Last batch, the host gets what's left
+    if end == state.items.len() {
+        add_payout(&mut payouts, &state.contract_owner, state.host_remainder)?;
+        state.host_remainder = Uint128(0);
+        state.status = LotteryStatus::Settled;
+    }
+
+    let contract_addr = deps.api.human_address(&env.contract.address)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (owner, amount) in payouts {
+        state.deposit = checked_sub(state.deposit, amount)?;
+        let to = deps.api.human_address(&owner)?;
+        messages.push(payout_msg(deps, &state, &contract_addr, to, amount)?);
+    }
+
+    config(&mut deps.storage).save(&state)?;
+
+    Ok(HandleResponse {
+        messages,
+        log: vec![],
+        data: None,
+    })
+}
+
+// ERC-721 interface
+
+/// @dev This emits when ownership of any NFT changes by any mechanism.
+///  This event emits when NFTs are created (`from` == 0) and destroyed
+///  (`to` == 0). Exception: during contract creation, any number of NFTs
+///  may be created and assigned without emitting Transfer. At the time of
+///  any transfer, the approved address for that NFT (if any) is reset to none.
+// fn transfer(from: CanonicalAddr, to: CanonicalAddr, token_id: u32) {
+//     unimplemented!()
+// }
+
+/// @dev This emits when the approved address for an NFT is changed or
+///  reaffirmed. The zero address indicates there is no approved address.
+///  When a Transfer event emits, this also indicates that the approved
+///  address for that NFT (if any) is reset to none.
+// event Approval(address indexed _owner, address indexed _approved, uint256 indexed _tokenId);
+
+/// @dev This emits when an operator is enabled or disabled for an owner.
+///  The operator can manage all NFTs of the owner.
+// event ApprovalForAll(address indexed _owner, address indexed _operator, bool _approved);
+
+/// @notice Count all NFTs assigned to an owner
+/// @dev NFTs assigned to the zero address are considered invalid, and this
+///  function throws for queries about the zero address.
+/// @param _owner An address for whom to query the balance
+/// @return The number of NFTs owned by `_owner`, possibly zero
+fn balance_of<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    owner: &HumanAddr,
+) -> StdResult<u32> {
+    let owner_addr_raw = deps.api.canonical_address(&owner)?;
+
+    if owner_addr_raw == *ZERO_ADDRESS {
+        return Err(ContractError::ZeroAddress {}.into());
+    }
+
+    let state = config_read(&deps.storage).load()?;
+    let mut count = 0;
+
+    for item in state.items {
+        if item.owner == owner_addr_raw {
+            count = count + 1;
         }
     }
 
     Ok(count)
 }
 
+fn set_viewing_key<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    key: &str,
+) -> StdResult<HandleResponse> {
+    viewing_keys(&mut deps.storage).save(env.message.sender.as_slice(), &hash_viewing_key(key))?;
+
+    Ok(HandleResponse::default())
+}
+
+fn hash_viewing_key(key: &str) -> Binary {
+    Binary(Sha256::digest(key.as_bytes()).to_vec())
+}
+
+/// Fails unless `viewing_key` is the key `address` has set
+fn check_viewing_key<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: &CanonicalAddr,
+    viewing_key: &str,
+) -> StdResult<()> {
+    let expected = viewing_keys_read(&deps.storage).may_load(address.as_slice())?;
+
+    if expected != Some(hash_viewing_key(viewing_key)) {
+        return Err(ContractError::InvalidViewingKey {}.into());
+    }
+
+    Ok(())
+}
+
+/// Ticket values are hidden until sales close if the host asked for it
+fn check_revealable(state: &State) -> StdResult<()> {
+    if state.reveal_after_close && state.status == LotteryStatus::Open {
+        return Err(ContractError::NotRevealedYet {}.into());
+    }
+
+    Ok(())
+}
+
+/// Tells `owner` what one of its tickets is worth
+fn reveal_ticket<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    state: &State,
+    owner: &CanonicalAddr,
+    ticket_id: u32,
+) -> StdResult<RevealResponse> {
+    check_revealable(state)?;
+
+    // Tickets drawn from a committed seed have no outcome until the draw
+    if state.status == LotteryStatus::Open
+        && state.seed_commitment.is_some()
+        && draws_from_reveal(&state.mode)
+    {
+        return Err(ContractError::NotRevealedYet {}.into());
+    }
+
+    if !is_token_id_valid(ticket_id, state) {
+        return Err(ContractError::InvalidTicket { ticket_id }.into());
+    }
+
+    if state.items[ticket_id as usize].owner != *owner {
+        return Err(ContractError::NotOwner {
+            address: deps.api.human_address(owner)?,
+            ticket_id,
+        }
+        .into());
+    }
+
+    let sealed_tickets = sealed_read(&deps.storage).load()?;
+    let value = sealed_tickets.values[ticket_id as usize];
+
+    // Tickets drawn from a single golden one win only if golden, lotto tickets if they're in a
+    // tier, and lootboxes and scratch cards whenever they hold something. Other tickets may
+    // still hold a consolation.
+    let winner = match state.mode {
+        GameMode::Classic | GameMode::Raffle | GameMode::Weighted => {
+            sealed_tickets.golden == Some(ticket_id)
+        }
+        GameMode::Lotto { .. } => match draw_result_read(&deps.storage).may_load()? {
+            Some(result) => result
+                .winners
+                .iter()
+                .any(|winner| winner.ticket_id == ticket_id),
+            None => false,
+        },
+        _ => value.u128() > 0,
+    };
+
+    Ok(RevealResponse {
+        ticket_id,
+        winner,
+        value: Coin {
+            denom: state.denom.clone(),
+            amount: value,
+        },
+    })
+}
+
+fn handle_reveal_ticket<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    env: Env,
+    ticket_id: u32,
+) -> StdResult<HandleResponse> {
+    let state = config_read(&deps.storage).load()?;
+    let reveal = reveal_ticket(deps, &state, &env.message.sender, ticket_id)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(to_binary(&reveal)?),
+    })
+}
+
+/// Same as `RevealTicket`, authenticated with the owner's viewing key
+fn query_reveal_ticket<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    ticket_id: u32,
+    viewing_key: &str,
+) -> StdResult<RevealResponse> {
+    let state = config_read(&deps.storage).load()?;
+
+    if !is_token_id_valid(ticket_id, &state) {
+        return Err(ContractError::InvalidTicket { ticket_id }.into());
+    }
+
+    let owner = state.items[ticket_id as usize].owner.clone();
+    check_viewing_key(deps, &owner, viewing_key)?;
+
+    reveal_ticket(deps, &state, &owner, ticket_id)
+}
+
+/// What `address` would get from the current outcome: the value of the tickets it holds, plus
+/// the remainder of the deposit if it's the host. Before the draw this is computed as if the
+/// lottery ended now.
+fn pending_payouts<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+    address: &HumanAddr,
+    viewing_key: &str,
+) -> StdResult<PendingPayoutsResponse> {
+    let address_raw = deps.api.canonical_address(address)?;
+    check_viewing_key(deps, &address_raw, viewing_key)?;
+
+    let state = config_read(&deps.storage).load()?;
+
+    // Pending payouts would tell who holds the winning tickets
+    check_revealable(&state)?;
+
+    // A cancelled lottery owes tickets their payment instead of their value, an open one
+    // consoles its losers the way `end_lottery` would
+    let mut sealed_tickets = sealed_read(&deps.storage).load()?;
+    let values = match state.status {
+        LotteryStatus::Refunding => sealed_tickets.paid,
+        LotteryStatus::Open => {
+            if state.consolation_bps > 0 {
+                award_consolation(&state, &mut sealed_tickets)?;
+            }
+            sealed_tickets.values
+        }
+        _ => sealed_tickets.values,
+    };
+    let mut ticket_ids = vec![];
+    let mut amount = Uint128(0);
+    let mut host_remainder = Uint128(0);
+
+    if state.status != LotteryStatus::Settled {
+        for item in state.items.iter() {
+            if is_sold(item, &state.contract_addr) && item.owner == address_raw {
+                ticket_ids.push(item.id);
+                amount = checked_add(amount, values[item.id as usize])?;
+            }
+        }
+
+        if address_raw == state.contract_owner {
+            host_remainder = match state.status {
+                // The beneficiaries take their share at the draw
+                LotteryStatus::Open => {
+                    let remainder = checked_sub(state.deposit, owed_to_tickets(&state, &values)?)?;
+                    let mut shared = Uint128(0);
+                    for payout in beneficiary_shares(&state, remainder)? {
+                        shared = checked_add(shared, payout.amount)?;
+                    }
+                    checked_sub(remainder, shared)?
+                }
+                _ => state.host_remainder,
+            };
+            amount = checked_add(amount, host_remainder)?;
+        }
+    }
+
+    Ok(PendingPayoutsResponse {
+        ticket_ids,
+        host_remainder,
+        amount: Coin {
+            denom: state.denom,
+            amount,
+        },
+    })
+}
+
+fn query_draw_result<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<DrawResultResponse> {
+    let result = match draw_result_read(&deps.storage).may_load()? {
+        Some(result) => result,
+        None => return Err(ContractError::NotDrawn {}.into()),
+    };
+
+    let mut winners = vec![];
+    for winner in result.winners {
+        winners.push(WinningTicketResponse {
+            ticket_id: winner.ticket_id,
+            tier: winner.tier,
+            owner: match winner.owner {
+                Some(owner) => Some(deps.api.human_address(&owner)?),
+                None => None,
+            },
+            payout: winner.payout,
+        });
+    }
+
+    let mut beneficiary_payouts = vec![];
+    for payout in result.beneficiary_payouts {
+        beneficiary_payouts.push(BeneficiaryPayoutResponse {
+            address: deps.api.human_address(&payout.address)?,
+            amount: payout.amount,
+        });
+    }
+
+    Ok(DrawResultResponse {
+        winners,
+        host_remainder: result.host_remainder,
+        drawn_at: result.drawn_at,
+        seed: result.seed,
+        entropy: result.entropy,
+        entries: result.entries,
+        numbers: result.numbers,
+        weights: result.weights,
+        beneficiary_payouts,
+    })
+}
+
+fn listings<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<Vec<ListingResponse>> {
+    let state = config_read(&deps.storage).load()?;
+
+    let mut listings = vec![];
+    for listing in state.listings.iter() {
+        listings.push(ListingResponse {
+            ticket_id: listing.ticket_id,
+            seller: deps.api.human_address(&listing.seller)?,
+            price: Coin {
+                denom: state.denom.clone(),
+                amount: listing.price,
+            },
+        });
+    }
+
+    Ok(listings)
+}
+
+fn info<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) -> StdResult<InfoResponse> {
+    let state = config_read(&deps.storage).load()?;
+
+    Ok(InfoResponse {
+        name: state.name.clone(),
+        host: deps.api.human_address(&state.contract_owner)?,
+        ticket_count: state.items.len() as u32,
+        winning_prize: state.winning_prize.clone(),
+        status: state.status.clone(),
+        claim_window: state.claim_window,
+        draw_at: state.draw_at,
+        freeze_blocks: state.freeze_blocks,
+        royalty_bps: state.royalty_bps,
+        royalty_recipient: deps.api.human_address(
+            state
+                .royalty_recipient
+                .as_ref()
+                .unwrap_or(&state.contract_owner),
+        )?,
+        beneficiaries: state
+            .beneficiaries
+            .iter()
+            .map(|beneficiary| {
+                Ok(BeneficiaryInfo {
+                    address: deps.api.human_address(&beneficiary.address)?,
+                    bps: beneficiary.bps,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?,
+    })
+}
+
+fn offers<S: Storage, A: Api, Q: Querier>(deps: &Extern<S, A, Q>) -> StdResult<Vec<OfferResponse>> {
+    let state = config_read(&deps.storage).load()?;
+
+    let mut offers = vec![];
+    for offer in state.offers.iter() {
+        offers.push(OfferResponse {
+            ticket_id: offer.ticket_id,
+            bidder: deps.api.human_address(&offer.bidder)?,
+            amount: Coin {
+                denom: state.denom.clone(),
+                amount: offer.amount,
+            },
+        });
+    }
+
+    Ok(offers)
+}
+
+/// Lists the prizes not claimed yet after the draw, and when they expire
+fn unclaimed_prizes<S: Storage, A: Api, Q: Querier>(
+    deps: &Extern<S, A, Q>,
+) -> StdResult<UnclaimedResponse> {
+    let state = config_read(&deps.storage).load()?;
+
+    let mut prizes = vec![];
+    if state.status == LotteryStatus::Drawn {
+        let values = sealed_read(&deps.storage).load()?.values;
+
+        // Tickets the contract holds were never sold, their value is part of the remainder.
+        // Owners aren't listed, the query is public
+        for item in state.items.iter() {
+            let amount = values[item.id as usize];
+            if !is_sold(item, &state.contract_addr) || amount.u128() == 0 {
+                continue;
+            }
+
+            prizes.push(UnclaimedPrize {
+                ticket_id: item.id,
+                amount,
+            });
+        }
+    }
+
+    Ok(UnclaimedResponse {
+        prizes,
+        host_remainder: state.host_remainder,
+        claim_deadline: claim_deadline(&state),
+    })
+}
+
 /// @notice Find the owner of an NFT
 /// @dev NFTs assigned to zero address are considered invalid, and queries
 ///  about them do throw.
@@ -250,20 +2839,20 @@ fn owner_of<S: Storage, A: Api, Q: Querier>(
 
     // Check to not go out of bounds
     if !is_token_id_valid(token_id, &state) {
-        return Err(throw_gen_err(format!(
-            "Item {:?} does not exist!",
-            token_id
-        )));
+        return Err(ContractError::InvalidTicket {
+            ticket_id: token_id,
+        }
+        .into());
     }
 
     let owner_addr_raw = state.items[token_id as usize].owner.clone();
 
     // Check if item has been redeemed
     if owner_addr_raw == *ZERO_ADDRESS {
-        return Err(throw_gen_err(format!(
-            "Item {:?} has been redeemed already!",
-            token_id
-        )));
+        return Err(ContractError::AlreadyRedeemed {
+            ticket_id: token_id,
+        }
+        .into());
     }
 
     deps.api.human_address(&owner_addr_raw)
@@ -295,48 +2884,45 @@ fn safe_transfer_from_with_data<S: Storage, A: Api, Q: Querier>(
 
     // Throw if `to` is the zero address
     if to_addr_raw == *ZERO_ADDRESS {
-        return Err(throw_gen_err(format!(
-            "Can't burn Items with `safe_transfer_from` function. To burn an Item, use the unsafe `transfer_ftom`"        )));
+        return Err(ContractError::ZeroAddress {}.into());
     }
 
     // Get item from state
-    let state = config_read(&mut deps.storage).load()?;
+    let state = config_read(&deps.storage).load()?;
+
+    check_not_frozen(&state, &env)?;
+
+    if !is_token_id_valid(token_id, &state) {
+        return Err(ContractError::InvalidTicket {
+            ticket_id: token_id,
+        }
+        .into());
+    }
+
     let item = state.items[token_id as usize].clone();
 
     // Check if owner or approved
     if !is_owner_or_approved(&item, &env.message.sender) {
-        return Err(StdError::Unauthorized { backtrace: None });
+        return Err(ContractError::Unauthorized {}.into());
     }
 
     // From has to be the owner
     if from_addr_raw != item.owner {
-        return Err(throw_gen_err(format!(
-            "{:?} is not the owner of {:?} Item!",
-            from, token_id
-        )));
-    }
-
-    if !is_token_id_valid(token_id, &state) {
-        return Err(throw_gen_err(format!(
-            "Item {:?} does not exist!",
-            token_id
-        )));
+        return Err(ContractError::NotOwner {
+            address: from.clone(),
+            ticket_id: token_id,
+        }
+        .into());
     }
 
     // Perform transfer
-    match perform_transfer(deps, &to_addr_raw, token_id) {
-        Ok(_) => Ok(HandleResponse {
-            messages: vec![],
-            log: vec![],
-            data: Some(Binary(data.to_vec())),
-        }),
-        Err(e) => {
-            return Err(throw_gen_err(format!(
-                "Error transferring Item {:?}: {:?}",
-                token_id, e
-            )))
-        }
-    }
+    perform_transfer(deps, &to_addr_raw, token_id)?;
+
+    Ok(HandleResponse {
+        messages: vec![],
+        log: vec![],
+        data: Some(Binary(data.to_vec())),
+    })
 }
 
 /// @notice Transfers the ownership of an NFT from one address to another address
@@ -391,17 +2977,17 @@ fn approve<S: Storage, A: Api, Q: Querier>(
     let mut state = config(&mut deps.storage).load()?;
 
     if !is_token_id_valid(token_id, &state) {
-        return Err(throw_gen_err(format!(
-            "Item {:?} does not exist!",
-            token_id
-        )));
+        return Err(ContractError::InvalidTicket {
+            ticket_id: token_id,
+        }
+        .into());
     }
 
     let mut item = state.items[token_id as usize].clone();
 
     // Check if owner or approved
     if !is_owner_or_approved(&item, &env.message.sender) {
-        return Err(StdError::Unauthorized { backtrace: None });
+        return Err(ContractError::Unauthorized {}.into());
     }
 
     if approved == *ZERO_ADDRESS {
@@ -442,3 +3028,1419 @@ fn get_approved(token_id: u32) -> CanonicalAddr {
 fn is_approved_for_all(owner: CanonicalAddr, operator: CanonicalAddr) -> bool {
     unimplemented!()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{coins, StdError};
+
+    use crate::msg::TokenInfo;
+
+    type Deps = Extern<MockStorage, MockApi, MockQuerier>;
+
+    fn init_msg(ticket_count: u32, golden: u32) -> InitMsg {
+        InitMsg {
+            name: "lottery".to_string(),
+            ticket_count,
+            golden,
+            denom: None,
+            token: None,
+            prize: None,
+            claim_window: None,
+            treasury: None,
+            seed_commitment: None,
+            royalty_bps: None,
+            royalty_recipient: None,
+            draw_at: None,
+            reveal_window: None,
+            freeze_blocks: None,
+            mode: None,
+            ticket_price: None,
+            prng_seed: None,
+            reveal_after_close: None,
+            min_tickets_sold: None,
+            consolation_bps: None,
+            beneficiaries: None,
+        }
+    }
+
+    fn try_init(msg: InitMsg, funds: &[Coin]) -> StdResult<InitResponse> {
+        let mut deps = mock_dependencies(20, &[]);
+        let env = mock_env(&deps.api, "creator", funds);
+        init(&mut deps, env, msg)
+    }
+
+    /// Creates the lottery with a prize of 1000uscrt
+    fn setup(msg: InitMsg) -> Deps {
+        let mut deps = mock_dependencies(20, &[]);
+        let env = mock_env(&deps.api, "creator", &coins(1000, "uscrt"));
+        init(&mut deps, env, msg).unwrap();
+        deps
+    }
+
+    fn run_at(
+        deps: &mut Deps,
+        sender: &str,
+        funds: u128,
+        height: u64,
+        msg: HandleMsg,
+    ) -> StdResult<HandleResponse> {
+        let sent = if funds > 0 {
+            coins(funds, "uscrt")
+        } else {
+            vec![]
+        };
+        let mut env = mock_env(&deps.api, sender, &sent);
+        env.block.height = height;
+        handle(deps, env, msg)
+    }
+
+    fn run(
+        deps: &mut Deps,
+        sender: &str,
+        funds: u128,
+        msg: HandleMsg,
+    ) -> StdResult<HandleResponse> {
+        run_at(deps, sender, funds, 12_345, msg)
+    }
+
+    fn buy(deps: &mut Deps, buyer: &str, ticket_id: u32, amount: u128) {
+        let msg = HandleMsg::BuyTicket {
+            ticket_id,
+            entropy: None,
+        };
+        run(deps, buyer, amount, msg).unwrap();
+    }
+
+    /// Bank sends of a response, as (recipient, amount)
+    fn sends(res: &HandleResponse) -> Vec<(HumanAddr, u128)> {
+        res.messages
+            .iter()
+            .map(|msg| match msg {
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address, amount, ..
+                }) => (to_address.clone(), amount[0].amount.u128()),
+                _ => panic!("unexpected message {:?}", msg),
+            })
+            .collect()
+    }
+
+    fn send(to: &str, amount: u128) -> (HumanAddr, u128) {
+        (HumanAddr::from(to), amount)
+    }
+
+    fn owner(deps: &Deps, ticket_id: u32) -> HumanAddr {
+        let state = config_read(&deps.storage).load().unwrap();
+        deps.api
+            .human_address(&state.items[ticket_id as usize].owner)
+            .unwrap()
+    }
+
+    /// SNIP-20 `Send` of `amount` from `from`, as the token calls it back
+    fn receive_msg(from: &str, amount: u128, msg: &ReceiveMsg) -> HandleMsg {
+        HandleMsg::Receive {
+            sender: HumanAddr::from(from),
+            from: HumanAddr::from(from),
+            amount: Uint128(amount),
+            msg: Some(to_binary(msg).unwrap()),
+        }
+    }
+
+    fn assert_error<T>(res: StdResult<T>, code: u16) {
+        match res {
+            Err(StdError::GenericErr { msg, .. }) => {
+                assert!(msg.starts_with(&format!("[{}]", code)), "{}", msg)
+            }
+            Err(err) => panic!("unexpected error {:?}", err),
+            Ok(_) => panic!("expected error {}", code),
+        }
+    }
+
+    #[test]
+    fn init_validation() {
+        let prize = coins(1000, "uscrt");
+        assert_error(try_init(init_msg(0, 0), &prize), 1013);
+        assert_error(try_init(init_msg(MAX_TICKET_COUNT + 1, 0), &prize), 1013);
+        assert_error(try_init(init_msg(3, 3), &prize), 1014);
+
+        // The prize is exactly one coin with a positive amount
+        assert_error(try_init(init_msg(3, 0), &[]), 1006);
+        let two_coins = vec![
+            Coin {
+                denom: "uscrt".to_string(),
+                amount: Uint128(500),
+            },
+            Coin {
+                denom: "uscrt".to_string(),
+                amount: Uint128(500),
+            },
+        ];
+        assert_error(try_init(init_msg(3, 0), &two_coins), 1015);
+        assert_error(try_init(init_msg(3, 0), &coins(0, "uscrt")), 1016);
+
+        try_init(init_msg(3, 2), &prize).unwrap();
+    }
+
+    #[test]
+    fn custom_denom() {
+        let mut msg = init_msg(3, 0);
+        msg.denom = Some("utest".to_string());
+        assert_error(try_init(msg.clone(), &coins(1000, "uscrt")), 1008);
+
+        let mut deps = mock_dependencies(20, &[]);
+        let env = mock_env(&deps.api, "creator", &coins(1000, "utest"));
+        init(&mut deps, env, msg).unwrap();
+
+        // Every coin sent has to be of the lottery's denom, and only one is accepted
+        let buy = HandleMsg::BuyTicket {
+            ticket_id: 0,
+            entropy: None,
+        };
+        let env = mock_env(&deps.api, "alice", &coins(10, "uscrt"));
+        assert_error(handle(&mut deps, env, buy.clone()), 1008);
+        let mixed = vec![
+            Coin {
+                denom: "utest".to_string(),
+                amount: Uint128(10),
+            },
+            Coin {
+                denom: "uscrt".to_string(),
+                amount: Uint128(10),
+            },
+        ];
+        let env = mock_env(&deps.api, "alice", &mixed);
+        assert_error(handle(&mut deps, env, buy.clone()), 1015);
+        let env = mock_env(&deps.api, "alice", &coins(10, "utest"));
+        handle(&mut deps, env, buy).unwrap();
+
+        let env = mock_env(&deps.api, "creator", &[]);
+        handle(&mut deps, env, HandleMsg::EndLottery { reveal: None }).unwrap();
+
+        // The prize is paid in the same denom
+        let claim = HandleMsg::ClaimPrize {
+            ticket_ids: vec![0],
+        };
+        let env = mock_env(&deps.api, "alice", &[]);
+        let res = handle(&mut deps, env, claim).unwrap();
+        match &res.messages[..] {
+            [CosmosMsg::Bank(BankMsg::Send {
+                to_address, amount, ..
+            })] => {
+                assert_eq!(to_address, &HumanAddr::from("alice"));
+                assert_eq!(amount, &coins(1000, "utest"));
+            }
+            messages => panic!("unexpected messages {:?}", messages),
+        }
+    }
+
+    #[test]
+    fn snip20_payments() {
+        let mut msg = init_msg(3, 0);
+        msg.token = Some(TokenInfo {
+            address: HumanAddr::from("token"),
+            code_hash: "hash".to_string(),
+        });
+        msg.prize = Some(Uint128(1000));
+
+        let mut deps = mock_dependencies(20, &[]);
+        let env = mock_env(&deps.api, "creator", &coins(1000, "uscrt"));
+        assert_error(init(&mut deps, env, msg.clone()), 1017);
+
+        // The token is asked to call us back on `Send`
+        let env = mock_env(&deps.api, "creator", &[]);
+        let res = init(&mut deps, env, msg).unwrap();
+        match &res.messages[..] {
+            [CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            })] => {
+                assert_eq!(contract_addr, &HumanAddr::from("token"));
+                let register: Snip20Msg = from_binary(msg).unwrap();
+                assert_eq!(
+                    register,
+                    Snip20Msg::RegisterReceive {
+                        code_hash: "hash".to_string(),
+                    }
+                );
+            }
+            messages => panic!("unexpected messages {:?}", messages),
+        }
+
+        // Only the token can call `Receive`, and nothing is sold before the prize is funded
+        let buy = ReceiveMsg::BuyTicket {
+            ticket_id: 0,
+            entropy: None,
+        };
+        assert_error(
+            run(&mut deps, "alice", 0, receive_msg("alice", 10, &buy)),
+            1005,
+        );
+        assert_error(
+            run(&mut deps, "token", 0, receive_msg("alice", 10, &buy)),
+            1019,
+        );
+
+        let fund = ReceiveMsg::FundPrize {};
+        assert_error(
+            run(&mut deps, "token", 0, receive_msg("alice", 1000, &fund)),
+            1005,
+        );
+        run(&mut deps, "token", 0, receive_msg("creator", 1000, &fund)).unwrap();
+
+        // Native funds would be lost
+        let native_buy = HandleMsg::BuyTicket {
+            ticket_id: 0,
+            entropy: None,
+        };
+        assert_error(run(&mut deps, "alice", 10, native_buy), 1017);
+
+        run(&mut deps, "token", 0, receive_msg("alice", 10, &buy)).unwrap();
+        run(
+            &mut deps,
+            "creator",
+            0,
+            HandleMsg::EndLottery { reveal: None },
+        )
+        .unwrap();
+
+        // Payouts are token transfers
+        let claim = HandleMsg::ClaimPrize {
+            ticket_ids: vec![0],
+        };
+        let res = run(&mut deps, "alice", 0, claim).unwrap();
+        match &res.messages[..] {
+            [CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            })] => {
+                assert_eq!(contract_addr, &HumanAddr::from("token"));
+                let transfer: Snip20Msg = from_binary(msg).unwrap();
+                assert_eq!(
+                    transfer,
+                    Snip20Msg::Transfer {
+                        recipient: HumanAddr::from("alice"),
+                        amount: Uint128(1000),
+                    }
+                );
+            }
+            messages => panic!("unexpected messages {:?}", messages),
+        }
+    }
+
+    #[test]
+    fn claim_prize_and_remainder() {
+        let mut deps = setup(init_msg(3, 1));
+        buy(&mut deps, "alice", 1, 10);
+        buy(&mut deps, "bob", 0, 10);
+
+        let claim = HandleMsg::ClaimPrize {
+            ticket_ids: vec![1],
+        };
+        assert_error(run(&mut deps, "alice", 0, claim.clone()), 1020);
+
+        run(
+            &mut deps,
+            "creator",
+            0,
+            HandleMsg::EndLottery { reveal: None },
+        )
+        .unwrap();
+
+        // Only the holder can claim, and only once
+        assert_error(run(&mut deps, "bob", 0, claim.clone()), 1004);
+        let res = run(&mut deps, "alice", 0, claim.clone()).unwrap();
+        assert_eq!(sends(&res), vec![send("alice", 1000)]);
+        assert_error(run(&mut deps, "alice", 0, claim), 1002);
+
+        // A losing ticket is worth nothing
+        let lose = HandleMsg::ClaimPrize {
+            ticket_ids: vec![0],
+        };
+        let res = run(&mut deps, "bob", 0, lose).unwrap();
+        assert_eq!(sends(&res), vec![]);
+
+        // The host gets the ticket sales
+        let remainder = HandleMsg::ClaimRemainder {};
+        assert_error(run(&mut deps, "alice", 0, remainder.clone()), 1005);
+        let res = run(&mut deps, "creator", 0, remainder.clone()).unwrap();
+        assert_eq!(sends(&res), vec![send("creator", 20)]);
+        assert_error(run(&mut deps, "creator", 0, remainder), 1021);
+
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(state.deposit, Uint128(0));
+    }
+
+    #[test]
+    fn settle_in_batches() {
+        let mut deps = setup(init_msg(3, 2));
+        buy(&mut deps, "alice", 0, 1);
+        buy(&mut deps, "bob", 1, 1);
+        buy(&mut deps, "alice", 2, 1);
+        run(
+            &mut deps,
+            "creator",
+            0,
+            HandleMsg::EndLottery { reveal: None },
+        )
+        .unwrap();
+
+        let batch = HandleMsg::SettleBatch { limit: 2 };
+        assert_error(run(&mut deps, "alice", 0, batch.clone()), 1005);
+
+        // Losing tickets are burned without payouts
+        let res = run(&mut deps, "creator", 0, batch.clone()).unwrap();
+        assert_eq!(sends(&res), vec![]);
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(state.settle_cursor, 2);
+        assert_eq!(state.status, LotteryStatus::Drawn);
+
+        // The last batch pays the host too
+        let res = run(&mut deps, "creator", 0, batch.clone()).unwrap();
+        assert_eq!(sends(&res), vec![send("alice", 1000), send("creator", 3)]);
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(state.status, LotteryStatus::Settled);
+        assert_eq!(state.deposit, Uint128(0));
+
+        assert_error(run(&mut deps, "creator", 0, batch), 1020);
+    }
+
+    #[test]
+    fn sweep_after_claim_window() {
+        let mut msg = init_msg(2, 0);
+        msg.claim_window = Some(100);
+        msg.treasury = Some(HumanAddr::from("treasury"));
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 0, 1);
+
+        let sweep = HandleMsg::SweepUnclaimed {};
+        assert_error(run_at(&mut deps, "creator", 0, 1_000, sweep.clone()), 1020);
+
+        let end = HandleMsg::EndLottery { reveal: None };
+        run_at(&mut deps, "creator", 0, 1_000, end).unwrap();
+
+        assert_error(run_at(&mut deps, "creator", 0, 1_050, sweep.clone()), 1023);
+        assert_error(run_at(&mut deps, "alice", 0, 1_100, sweep.clone()), 1005);
+
+        // Prizes can't be claimed once the window is over
+        let claim = HandleMsg::ClaimPrize {
+            ticket_ids: vec![0],
+        };
+        assert_error(run_at(&mut deps, "alice", 0, 1_100, claim), 1022);
+
+        let res = run_at(&mut deps, "treasury", 0, 1_100, sweep).unwrap();
+        assert_eq!(sends(&res), vec![send("treasury", 1001)]);
+
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(state.status, LotteryStatus::Settled);
+        assert_eq!(state.deposit, Uint128(0));
+    }
+
+    #[test]
+    fn sweep_needs_claim_window() {
+        let mut deps = setup(init_msg(2, 0));
+        run(
+            &mut deps,
+            "creator",
+            0,
+            HandleMsg::EndLottery { reveal: None },
+        )
+        .unwrap();
+
+        assert_error(
+            run(&mut deps, "creator", 0, HandleMsg::SweepUnclaimed {}),
+            1024,
+        );
+    }
+
+    #[test]
+    fn unclaimed_prizes() {
+        let mut msg = init_msg(3, 0);
+        msg.claim_window = Some(100);
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 0, 10);
+        buy(&mut deps, "bob", 1, 10);
+
+        let end = HandleMsg::EndLottery { reveal: None };
+        run_at(&mut deps, "creator", 0, 1_000, end).unwrap();
+
+        // Losing tickets aren't listed
+        let unclaimed: UnclaimedResponse =
+            from_binary(&query(&deps, QueryMsg::UnclaimedPrizes {}).unwrap()).unwrap();
+        assert_eq!(
+            unclaimed,
+            UnclaimedResponse {
+                prizes: vec![UnclaimedPrize {
+                    ticket_id: 0,
+                    amount: Uint128(1000),
+                }],
+                host_remainder: Uint128(20),
+                claim_deadline: Some(1_100),
+            }
+        );
+
+        let claim = HandleMsg::ClaimPrize {
+            ticket_ids: vec![0],
+        };
+        run_at(&mut deps, "alice", 0, 1_050, claim).unwrap();
+        let unclaimed: UnclaimedResponse =
+            from_binary(&query(&deps, QueryMsg::UnclaimedPrizes {}).unwrap()).unwrap();
+        assert_eq!(unclaimed.prizes, vec![]);
+    }
+
+    #[test]
+    fn draw_result() {
+        let mut deps = setup(init_msg(3, 1));
+        buy(&mut deps, "alice", 1, 10);
+        assert_error(query(&deps, QueryMsg::DrawResult {}), 1020);
+
+        let end = HandleMsg::EndLottery { reveal: None };
+        run_at(&mut deps, "creator", 0, 1_000, end).unwrap();
+
+        // The winner is kept once the ticket is claimed and burned
+        let claim = HandleMsg::ClaimPrize {
+            ticket_ids: vec![1],
+        };
+        run(&mut deps, "alice", 0, claim).unwrap();
+
+        let result: DrawResultResponse =
+            from_binary(&query(&deps, QueryMsg::DrawResult {}).unwrap()).unwrap();
+        assert_eq!(
+            result,
+            DrawResultResponse {
+                winners: vec![WinningTicketResponse {
+                    ticket_id: 1,
+                    tier: 0,
+                    owner: Some(HumanAddr::from("alice")),
+                    payout: Coin {
+                        denom: "uscrt".to_string(),
+                        amount: Uint128(1000),
+                    },
+                }],
+                host_remainder: Uint128(10),
+                drawn_at: 1_000,
+                seed: None,
+                entropy: None,
+                entries: vec![],
+                numbers: vec![],
+                weights: vec![],
+                beneficiary_payouts: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn draw_result_unsold_golden() {
+        let mut deps = setup(init_msg(3, 2));
+        buy(&mut deps, "alice", 1, 10);
+        run(
+            &mut deps,
+            "creator",
+            0,
+            HandleMsg::EndLottery { reveal: None },
+        )
+        .unwrap();
+
+        // Nobody holds the golden ticket, the host keeps the prize
+        let result: DrawResultResponse =
+            from_binary(&query(&deps, QueryMsg::DrawResult {}).unwrap()).unwrap();
+        let winners: Vec<(u32, Option<HumanAddr>)> = result
+            .winners
+            .iter()
+            .map(|winner| (winner.ticket_id, winner.owner.clone()))
+            .collect();
+        assert_eq!(winners, vec![(2, None)]);
+        assert_eq!(result.host_remainder, Uint128(1010));
+    }
+
+    #[test]
+    fn committed_draw_is_verifiable() {
+        let seed = b"secret seed".to_vec();
+        let commitment = Sha256::digest(seed.as_slice()).to_vec();
+        let mut msg = init_msg(4, 0);
+        msg.seed_commitment = Some(Binary(commitment.clone()));
+        msg.draw_at = Some(20_000);
+        let mut deps = setup(msg);
+
+        let buy_with = |deps: &mut Deps, buyer: &str, ticket_id: u32| {
+            let msg = HandleMsg::BuyTicket {
+                ticket_id,
+                entropy: Some(Binary(buyer.as_bytes().to_vec())),
+            };
+            run(deps, buyer, 10, msg).unwrap();
+        };
+        buy_with(&mut deps, "alice", 0);
+        buy_with(&mut deps, "bob", 2);
+
+        // Sales close at draw_at, from then on anyone can draw with the right seed
+        let late_buy = HandleMsg::BuyTicket {
+            ticket_id: 1,
+            entropy: None,
+        };
+        assert_error(run_at(&mut deps, "carol", 10, 20_000, late_buy), 1009);
+        let end = |reveal: Option<Vec<u8>>| HandleMsg::EndLottery {
+            reveal: reveal.map(Binary),
+        };
+        assert_error(
+            run_at(&mut deps, "carol", 0, 19_999, end(Some(seed.clone()))),
+            1035,
+        );
+        assert_error(run_at(&mut deps, "carol", 0, 20_000, end(None)), 1026);
+        assert_error(
+            run_at(&mut deps, "carol", 0, 20_000, end(Some(b"guess".to_vec()))),
+            1026,
+        );
+        run_at(&mut deps, "carol", 0, 20_000, end(Some(seed.clone()))).unwrap();
+
+        // Replaying the published entries from the commitment gives the same golden ticket
+        let result: DrawResultResponse =
+            from_binary(&query(&deps, QueryMsg::DrawResult {}).unwrap()).unwrap();
+        assert_eq!(result.seed, Some(Binary(seed.clone())));
+        let alice = deps
+            .api
+            .canonical_address(&HumanAddr::from("alice"))
+            .unwrap();
+        let alice_entropy = Some(Binary(b"alice".to_vec()));
+        assert_eq!(
+            result.entries[0],
+            Binary(entropy_entry(&alice, 0, 12_345, &alice_entropy))
+        );
+        assert_eq!(result.entries.len(), 2);
+
+        let mut entropy = commitment;
+        for entry in result.entries.iter() {
+            entropy = mix_entropy(&entropy, entry.as_slice());
+        }
+        assert_eq!(result.entropy, Some(Binary(entropy.clone())));
+        assert_eq!(result.winners.len(), 1);
+        assert_eq!(result.winners[0].ticket_id, draw_ticket(&seed, &entropy, 4));
+    }
+
+    fn listings_of(deps: &Deps) -> Vec<ListingResponse> {
+        from_binary(&query(deps, QueryMsg::Listings {}).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn listings() {
+        let mut deps = setup(init_msg(2, 0));
+        buy(&mut deps, "alice", 0, 10);
+
+        let list = |price: u128| HandleMsg::ListTicket {
+            ticket_id: 0,
+            price: Uint128(price),
+        };
+        assert_error(run(&mut deps, "bob", 0, list(50)), 1004);
+        run(&mut deps, "alice", 0, list(50)).unwrap();
+        assert_eq!(
+            listings_of(&deps),
+            vec![ListingResponse {
+                ticket_id: 0,
+                seller: HumanAddr::from("alice"),
+                price: Coin {
+                    denom: "uscrt".to_string(),
+                    amount: Uint128(50),
+                },
+            }]
+        );
+        let delist = HandleMsg::Delist { ticket_id: 0 };
+        assert_error(run(&mut deps, "bob", 0, delist), 1005);
+
+        // The buyer has to send the exact price, which goes to the seller
+        let buy_listed = HandleMsg::BuyListed { ticket_id: 0 };
+        assert_error(run(&mut deps, "bob", 40, buy_listed.clone()), 1029);
+        let res = run(&mut deps, "bob", 50, buy_listed.clone()).unwrap();
+        assert_eq!(sends(&res), vec![send("alice", 50)]);
+        assert_eq!(owner(&deps, 0), HumanAddr::from("bob"));
+        assert_error(run(&mut deps, "charlie", 50, buy_listed), 1027);
+
+        // A transfer drops the listing too
+        run(&mut deps, "bob", 0, list(60)).unwrap();
+        let transfer = HandleMsg::SafeTransferFrom {
+            from: HumanAddr::from("bob"),
+            to: HumanAddr::from("charlie"),
+            ticket_id: 0,
+        };
+        run(&mut deps, "bob", 0, transfer).unwrap();
+        assert_eq!(listings_of(&deps), vec![]);
+
+        // Trading is over once the lottery is drawn
+        run(&mut deps, "charlie", 0, list(70)).unwrap();
+        run(
+            &mut deps,
+            "creator",
+            0,
+            HandleMsg::EndLottery { reveal: None },
+        )
+        .unwrap();
+        assert_eq!(listings_of(&deps), vec![]);
+        assert_error(run(&mut deps, "charlie", 0, list(70)), 1009);
+    }
+
+    #[test]
+    fn offers() {
+        let mut deps = setup(init_msg(3, 0));
+        buy(&mut deps, "alice", 0, 10);
+
+        let offer = HandleMsg::MakeOffer { ticket_id: 0 };
+        let unsold = HandleMsg::MakeOffer { ticket_id: 1 };
+        assert_error(run(&mut deps, "bob", 10, unsold), 1032);
+
+        // A higher offer refunds the previous bidder
+        run(&mut deps, "bob", 30, offer.clone()).unwrap();
+        assert_error(run(&mut deps, "charlie", 20, offer.clone()), 1031);
+        let res = run(&mut deps, "charlie", 40, offer.clone()).unwrap();
+        assert_eq!(sends(&res), vec![send("bob", 30)]);
+        let offers: Vec<OfferResponse> =
+            from_binary(&query(&deps, QueryMsg::Offers {}).unwrap()).unwrap();
+        assert_eq!(
+            offers,
+            vec![OfferResponse {
+                ticket_id: 0,
+                bidder: HumanAddr::from("charlie"),
+                amount: Coin {
+                    denom: "uscrt".to_string(),
+                    amount: Uint128(40),
+                },
+            }]
+        );
+
+        let cancel = HandleMsg::CancelOffer { ticket_id: 0 };
+        let accept = HandleMsg::AcceptOffer { ticket_id: 0 };
+        assert_error(run(&mut deps, "bob", 0, cancel.clone()), 1005);
+        assert_error(run(&mut deps, "bob", 0, accept.clone()), 1004);
+        let res = run(&mut deps, "alice", 0, accept).unwrap();
+        assert_eq!(sends(&res), vec![send("alice", 40)]);
+        assert_eq!(owner(&deps, 0), HumanAddr::from("charlie"));
+
+        run(&mut deps, "dave", 50, offer.clone()).unwrap();
+        let res = run(&mut deps, "dave", 0, cancel).unwrap();
+        assert_eq!(sends(&res), vec![send("dave", 50)]);
+
+        // Offers still open at the draw are refunded
+        run(&mut deps, "erin", 60, offer).unwrap();
+        let res = run(
+            &mut deps,
+            "creator",
+            0,
+            HandleMsg::EndLottery { reveal: None },
+        )
+        .unwrap();
+        assert_eq!(sends(&res), vec![send("erin", 60)]);
+        let offers: Vec<OfferResponse> =
+            from_binary(&query(&deps, QueryMsg::Offers {}).unwrap()).unwrap();
+        assert_eq!(offers, vec![]);
+    }
+
+    #[test]
+    fn royalties() {
+        let mut msg = init_msg(2, 0);
+        msg.royalty_bps = Some(10_001);
+        assert_error(try_init(msg, &coins(1000, "uscrt")), 1033);
+
+        // Without a recipient the royalty goes to the host's payout
+        let mut msg = init_msg(2, 0);
+        msg.royalty_bps = Some(1_000);
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 0, 10);
+
+        let list = HandleMsg::ListTicket {
+            ticket_id: 0,
+            price: Uint128(50),
+        };
+        run(&mut deps, "alice", 0, list).unwrap();
+        let buy_listed = HandleMsg::BuyListed { ticket_id: 0 };
+        let res = run(&mut deps, "bob", 50, buy_listed).unwrap();
+        assert_eq!(sends(&res), vec![send("alice", 45)]);
+
+        // Sales to an offer too
+        run(
+            &mut deps,
+            "charlie",
+            40,
+            HandleMsg::MakeOffer { ticket_id: 0 },
+        )
+        .unwrap();
+        let res = run(&mut deps, "bob", 0, HandleMsg::AcceptOffer { ticket_id: 0 }).unwrap();
+        assert_eq!(sends(&res), vec![send("bob", 36)]);
+
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(state.deposit, Uint128(1_019));
+
+        // With a recipient it's paid right away
+        let mut msg = init_msg(2, 0);
+        msg.royalty_bps = Some(1_000);
+        msg.royalty_recipient = Some(HumanAddr::from("artist"));
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 0, 10);
+
+        let list = HandleMsg::ListTicket {
+            ticket_id: 0,
+            price: Uint128(50),
+        };
+        run(&mut deps, "alice", 0, list).unwrap();
+        let buy_listed = HandleMsg::BuyListed { ticket_id: 0 };
+        let res = run(&mut deps, "bob", 50, buy_listed).unwrap();
+        assert_eq!(sends(&res), vec![send("artist", 5), send("alice", 45)]);
+
+        let info: InfoResponse = from_binary(&query(&deps, QueryMsg::Info {}).unwrap()).unwrap();
+        assert_eq!(info.royalty_bps, 1_000);
+        assert_eq!(info.royalty_recipient, HumanAddr::from("artist"));
+    }
+
+    #[test]
+    fn transfer_freeze() {
+        let mut msg = init_msg(2, 0);
+        msg.freeze_blocks = Some(100);
+        assert_error(try_init(msg.clone(), &coins(1000, "uscrt")), 1049);
+
+        msg.draw_at = Some(20_000);
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 0, 10);
+
+        let list = HandleMsg::ListTicket {
+            ticket_id: 0,
+            price: Uint128(50),
+        };
+        run_at(&mut deps, "alice", 0, 19_000, list.clone()).unwrap();
+
+        // Tickets can't change hands during the last 100 blocks before the draw
+        let transfer = HandleMsg::SafeTransferFrom {
+            from: HumanAddr::from("alice"),
+            to: HumanAddr::from("bob"),
+            ticket_id: 0,
+        };
+        let buy_listed = HandleMsg::BuyListed { ticket_id: 0 };
+        assert_error(
+            run_at(&mut deps, "alice", 0, 19_900, transfer.clone()),
+            1036,
+        );
+        assert_error(run_at(&mut deps, "bob", 50, 19_900, buy_listed), 1036);
+        assert_error(run_at(&mut deps, "alice", 0, 19_900, list), 1036);
+
+        run_at(&mut deps, "alice", 0, 19_899, transfer).unwrap();
+        assert_eq!(owner(&deps, 0), HumanAddr::from("bob"));
+    }
+
+    #[test]
+    fn lootbox() {
+        let mut msg = init_msg(0, 0);
+        msg.mode = Some(GameMode::Lootbox {
+            loot_table: vec![Loot {
+                amount: Uint128(300),
+                weight: 1,
+                supply: 2,
+            }],
+        });
+        msg.prng_seed = Some(Binary(b"seed".to_vec()));
+        let mut deps = setup(msg);
+
+        let buy_box = HandleMsg::BuyLootbox { entropy: None };
+        let res = run(&mut deps, "alice", 10, buy_box.clone()).unwrap();
+        let ticket_id: u32 = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(ticket_id, 0);
+        run(&mut deps, "bob", 10, buy_box.clone()).unwrap();
+        assert_error(run(&mut deps, "charlie", 10, buy_box), 1039);
+
+        let list = HandleMsg::ListTicket {
+            ticket_id: 0,
+            price: Uint128(50),
+        };
+        run(&mut deps, "alice", 0, list).unwrap();
+        run(&mut deps, "bob", 20, HandleMsg::MakeOffer { ticket_id: 0 }).unwrap();
+
+        // Opening burns the box, its offer is refunded and its listing dropped
+        let open = HandleMsg::OpenLootbox { ticket_id: 0 };
+        assert_error(run(&mut deps, "bob", 0, open.clone()), 1004);
+        let res = run(&mut deps, "alice", 0, open.clone()).unwrap();
+        assert_eq!(sends(&res), vec![send("bob", 20), send("alice", 300)]);
+        let content: Coin = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(content.amount, Uint128(300));
+
+        assert_error(
+            run(&mut deps, "bob", 50, HandleMsg::BuyListed { ticket_id: 0 }),
+            1027,
+        );
+        assert_error(run(&mut deps, "alice", 0, open), 1002);
+    }
+
+    #[test]
+    fn snip20_lootbox_stays_funded() {
+        let mut msg = init_msg(0, 0);
+        msg.mode = Some(GameMode::Lootbox {
+            loot_table: vec![Loot {
+                amount: Uint128(300),
+                weight: 1,
+                supply: 2,
+            }],
+        });
+        msg.prng_seed = Some(Binary(b"seed".to_vec()));
+        msg.token = Some(TokenInfo {
+            address: HumanAddr::from("token"),
+            code_hash: "hash".to_string(),
+        });
+        msg.prize = Some(Uint128(600));
+
+        let mut deps = mock_dependencies(20, &[]);
+        let env = mock_env(&deps.api, "creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let fund = ReceiveMsg::FundPrize {};
+        run(&mut deps, "token", 0, receive_msg("creator", 600, &fund)).unwrap();
+
+        // Opening a box lowers the deposit below the pool, sales go on
+        let buy_box = ReceiveMsg::BuyLootbox { entropy: None };
+        run(&mut deps, "token", 0, receive_msg("alice", 10, &buy_box)).unwrap();
+        run(
+            &mut deps,
+            "alice",
+            0,
+            HandleMsg::OpenLootbox { ticket_id: 0 },
+        )
+        .unwrap();
+        run(&mut deps, "token", 0, receive_msg("bob", 10, &buy_box)).unwrap();
+
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(state.deposit, Uint128(320));
+    }
+
+    fn reveal(deps: &mut Deps, sender: &str, ticket_id: u32) -> StdResult<RevealResponse> {
+        let res = run(deps, sender, 0, HandleMsg::RevealTicket { ticket_id })?;
+        Ok(from_binary(&res.data.unwrap()).unwrap())
+    }
+
+    #[test]
+    fn reveal_tickets() {
+        let mut deps = setup(init_msg(3, 1));
+        buy(&mut deps, "alice", 1, 10);
+        buy(&mut deps, "bob", 0, 10);
+
+        // Only the owner learns what a ticket is worth
+        let outcome = reveal(&mut deps, "alice", 1).unwrap();
+        assert_eq!(
+            outcome,
+            RevealResponse {
+                ticket_id: 1,
+                winner: true,
+                value: Coin {
+                    denom: "uscrt".to_string(),
+                    amount: Uint128(1000),
+                },
+            }
+        );
+        assert_error(reveal(&mut deps, "bob", 1), 1004);
+
+        // Or whoever has the owner's viewing key
+        let key = HandleMsg::SetViewingKey {
+            key: "key".to_string(),
+        };
+        run(&mut deps, "bob", 0, key).unwrap();
+        let query_reveal = |viewing_key: &str| QueryMsg::RevealTicket {
+            ticket_id: 0,
+            viewing_key: viewing_key.to_string(),
+        };
+        assert_error(query(&deps, query_reveal("guess")), 1025);
+        let outcome: RevealResponse =
+            from_binary(&query(&deps, query_reveal("key")).unwrap()).unwrap();
+        assert!(!outcome.winner);
+        assert_eq!(outcome.value.amount, Uint128(0));
+    }
+
+    #[test]
+    fn reveal_after_close() {
+        let mut msg = init_msg(3, 1);
+        msg.reveal_after_close = Some(true);
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 1, 10);
+
+        assert_error(reveal(&mut deps, "alice", 1), 1041);
+        run(
+            &mut deps,
+            "creator",
+            0,
+            HandleMsg::EndLottery { reveal: None },
+        )
+        .unwrap();
+        assert!(reveal(&mut deps, "alice", 1).unwrap().winner);
+    }
+
+    #[test]
+    fn committed_tickets_reveal_after_draw() {
+        let seed = b"secret seed".to_vec();
+        let mut msg = init_msg(1, 0);
+        msg.seed_commitment = Some(Binary(Sha256::digest(seed.as_slice()).to_vec()));
+        msg.draw_at = Some(20_000);
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 0, 10);
+
+        // The golden ticket isn't drawn yet, a loss would be a lie
+        assert_error(reveal(&mut deps, "alice", 0), 1041);
+
+        let end = HandleMsg::EndLottery {
+            reveal: Some(Binary(seed)),
+        };
+        run_at(&mut deps, "creator", 0, 20_000, end).unwrap();
+        assert!(reveal(&mut deps, "alice", 0).unwrap().winner);
+    }
+
+    #[test]
+    fn scratch_cards() {
+        let seed = b"seed".to_vec();
+        let mut msg = init_msg(0, 0);
+        msg.mode = Some(GameMode::ScratchCard {
+            tiers: vec![ScratchTier {
+                amount: Uint128(400),
+                odds: ODDS_DENOMINATOR,
+            }],
+        });
+        msg.seed_commitment = Some(Binary(Sha256::digest(seed.as_slice()).to_vec()));
+        msg.prng_seed = Some(Binary(seed.clone()));
+        let mut deps = setup(msg);
+
+        // Every card wins until the pool can't cover another prize
+        let mut outcomes = vec![];
+        for _ in 0..3 {
+            let buy_card = HandleMsg::BuyScratchCard { entropy: None };
+            let res = run(&mut deps, "alice", 10, buy_card).unwrap();
+            let outcome: RevealResponse = from_binary(&res.data.unwrap()).unwrap();
+            outcomes.push(outcome.winner);
+        }
+        assert_eq!(outcomes, vec![true, true, false]);
+
+        // Wins are claimed right away
+        let claim = HandleMsg::ClaimPrize {
+            ticket_ids: vec![0],
+        };
+        let res = run(&mut deps, "alice", 0, claim).unwrap();
+        assert_eq!(sends(&res), vec![send("alice", 400)]);
+
+        run(
+            &mut deps,
+            "creator",
+            0,
+            HandleMsg::EndLottery { reveal: None },
+        )
+        .unwrap();
+
+        // The claimed card is still recorded, with its buyer
+        let result: DrawResultResponse =
+            from_binary(&query(&deps, QueryMsg::DrawResult {}).unwrap()).unwrap();
+        let winners: Vec<(u32, Option<HumanAddr>)> = result
+            .winners
+            .iter()
+            .map(|winner| (winner.ticket_id, winner.owner.clone()))
+            .collect();
+        assert_eq!(
+            winners,
+            vec![
+                (0, Some(HumanAddr::from("alice"))),
+                (1, Some(HumanAddr::from("alice"))),
+            ]
+        );
+        assert_eq!(result.seed, Some(Binary(seed)));
+        assert_eq!(result.host_remainder, Uint128(230));
+    }
+
+    #[test]
+    fn snip20_scratch_cards_stay_funded() {
+        let seed = b"seed".to_vec();
+        let mut msg = init_msg(0, 0);
+        msg.mode = Some(GameMode::ScratchCard {
+            tiers: vec![ScratchTier {
+                amount: Uint128(400),
+                odds: ODDS_DENOMINATOR,
+            }],
+        });
+        msg.seed_commitment = Some(Binary(Sha256::digest(seed.as_slice()).to_vec()));
+        msg.prng_seed = Some(Binary(seed));
+        msg.token = Some(TokenInfo {
+            address: HumanAddr::from("token"),
+            code_hash: "hash".to_string(),
+        });
+        msg.prize = Some(Uint128(1000));
+
+        let mut deps = mock_dependencies(20, &[]);
+        let env = mock_env(&deps.api, "creator", &[]);
+        init(&mut deps, env, msg).unwrap();
+
+        let fund = ReceiveMsg::FundPrize {};
+        run(&mut deps, "token", 0, receive_msg("creator", 1000, &fund)).unwrap();
+
+        // A claimed win lowers the deposit below the prize, sales go on
+        let buy_card = ReceiveMsg::BuyScratchCard { entropy: None };
+        run(&mut deps, "token", 0, receive_msg("alice", 10, &buy_card)).unwrap();
+        let claim = HandleMsg::ClaimPrize {
+            ticket_ids: vec![0],
+        };
+        run(&mut deps, "alice", 0, claim).unwrap();
+        run(&mut deps, "token", 0, receive_msg("bob", 10, &buy_card)).unwrap();
+
+        let state = config_read(&deps.storage).load().unwrap();
+        assert_eq!(state.deposit, Uint128(620));
+    }
+
+    #[test]
+    fn lotto() {
+        let seed = b"secret seed".to_vec();
+        let mut msg = init_msg(0, 0);
+        msg.mode = Some(GameMode::Lotto {
+            pick_count: 2,
+            max_number: 3,
+            tiers: vec![
+                LottoTier {
+                    matches: 2,
+                    share_bps: 6_000,
+                },
+                LottoTier {
+                    matches: 1,
+                    share_bps: 2_000,
+                },
+            ],
+        });
+        msg.draw_at = Some(20_000);
+        assert_error(try_init(msg.clone(), &coins(1000, "uscrt")), 1045);
+
+        msg.seed_commitment = Some(Binary(Sha256::digest(seed.as_slice()).to_vec()));
+        let mut deps = setup(msg);
+
+        let buy_numbers = |numbers: Vec<u8>| HandleMsg::BuyLottoTicket {
+            numbers,
+            entropy: None,
+        };
+        assert_error(run(&mut deps, "alice", 10, buy_numbers(vec![1, 1])), 1044);
+        assert_error(run(&mut deps, "alice", 10, buy_numbers(vec![1, 4])), 1044);
+        let classic_buy = HandleMsg::BuyTicket {
+            ticket_id: 0,
+            entropy: None,
+        };
+        assert_error(run(&mut deps, "alice", 10, classic_buy), 1037);
+
+        run(&mut deps, "alice", 10, buy_numbers(vec![2, 1])).unwrap();
+        run(&mut deps, "bob", 10, buy_numbers(vec![2, 3])).unwrap();
+        run(&mut deps, "carol", 10, buy_numbers(vec![1, 3])).unwrap();
+        assert_error(reveal(&mut deps, "alice", 0), 1041);
+
+        let end = HandleMsg::EndLottery {
+            reveal: Some(Binary(seed.clone())),
+        };
+        run_at(&mut deps, "carol", 0, 20_000, end).unwrap();
+
+        let result: DrawResultResponse =
+            from_binary(&query(&deps, QueryMsg::DrawResult {}).unwrap()).unwrap();
+        let entropy = result.entropy.clone().unwrap();
+        assert_eq!(
+            result.numbers,
+            draw_numbers(&seed, entropy.as_slice(), 2, 3)
+        );
+
+        // Two of three numbers are drawn, so one ticket matches both and the others one each
+        let picks = vec![vec![1, 2], vec![2, 3], vec![1, 3]];
+        let jackpot = picks
+            .iter()
+            .position(|numbers| *numbers == result.numbers)
+            .unwrap() as u32;
+        let mut payouts: Vec<(u32, u32, u128)> = result
+            .winners
+            .iter()
+            .map(|winner| (winner.ticket_id, winner.tier, winner.payout.amount.u128()))
+            .collect();
+        payouts.sort();
+        let expected: Vec<(u32, u32, u128)> = (0..3)
+            .map(|ticket_id| {
+                if ticket_id == jackpot {
+                    (ticket_id, 0, 600)
+                } else {
+                    (ticket_id, 1, 100)
+                }
+            })
+            .collect();
+        assert_eq!(payouts, expected);
+        assert_eq!(result.host_remainder, Uint128(230));
+
+        let holders = ["alice", "bob", "carol"];
+        let winner = reveal(&mut deps, holders[jackpot as usize], jackpot).unwrap();
+        assert!(winner.winner);
+        assert_eq!(winner.value.amount, Uint128(600));
+    }
+
+    /// Lottery in `mode` drawn at block 20000 from a committed `seed`
+    fn setup_committed(mode: GameMode, seed: &[u8]) -> Deps {
+        let mut msg = init_msg(0, 0);
+        msg.mode = Some(mode);
+        msg.seed_commitment = Some(Binary(Sha256::digest(seed).to_vec()));
+        msg.draw_at = Some(20_000);
+        setup(msg)
+    }
+
+    #[test]
+    fn raffle() {
+        let seed = b"secret seed".to_vec();
+        let mut deps = setup_committed(GameMode::Raffle, &seed);
+
+        let holders = ["alice", "bob", "carol"];
+        for (i, holder) in holders.iter().enumerate() {
+            let buy_raffle = HandleMsg::BuyRaffleTicket { entropy: None };
+            let res = run(&mut deps, holder, 10, buy_raffle).unwrap();
+            let ticket_id: u32 = from_binary(&res.data.unwrap()).unwrap();
+            assert_eq!(ticket_id, i as u32);
+        }
+        assert_error(reveal(&mut deps, "alice", 0), 1041);
+
+        let end = HandleMsg::EndLottery {
+            reveal: Some(Binary(seed.clone())),
+        };
+        run_at(&mut deps, "alice", 0, 20_000, end).unwrap();
+
+        // The winner is drawn among the sold tickets only
+        let result: DrawResultResponse =
+            from_binary(&query(&deps, QueryMsg::DrawResult {}).unwrap()).unwrap();
+        let entropy = result.entropy.clone().unwrap();
+        let golden = draw_ticket(&seed, entropy.as_slice(), 3);
+        let winners: Vec<(u32, Option<HumanAddr>)> = result
+            .winners
+            .iter()
+            .map(|winner| (winner.ticket_id, winner.owner.clone()))
+            .collect();
+        assert_eq!(
+            winners,
+            vec![(golden, Some(HumanAddr::from(holders[golden as usize])))]
+        );
+        assert_eq!(result.host_remainder, Uint128(30));
+        assert!(
+            reveal(&mut deps, holders[golden as usize], golden)
+                .unwrap()
+                .winner
+        );
+    }
+
+    #[test]
+    fn raffle_without_sales() {
+        let seed = b"secret seed".to_vec();
+        let mut deps = setup_committed(GameMode::Raffle, &seed);
+
+        let end = HandleMsg::EndLottery {
+            reveal: Some(Binary(seed)),
+        };
+        run_at(&mut deps, "creator", 0, 20_000, end).unwrap();
+
+        let result: DrawResultResponse =
+            from_binary(&query(&deps, QueryMsg::DrawResult {}).unwrap()).unwrap();
+        assert_eq!(result.winners, vec![]);
+        assert_eq!(result.host_remainder, Uint128(1000));
+    }
+
+    #[test]
+    fn weighted() {
+        let seed = b"secret seed".to_vec();
+        let mut deps = setup_committed(GameMode::Weighted, &seed);
+
+        // Each address has a single entry, weighted by everything it contributed
+        let contribute = HandleMsg::Contribute { entropy: None };
+        run(&mut deps, "alice", 10, contribute.clone()).unwrap();
+        run(&mut deps, "bob", 5, contribute.clone()).unwrap();
+        let res = run(&mut deps, "alice", 20, contribute).unwrap();
+        let ticket_id: u32 = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(ticket_id, 0);
+        assert_error(reveal(&mut deps, "alice", 0), 1041);
+
+        let end = HandleMsg::EndLottery {
+            reveal: Some(Binary(seed.clone())),
+        };
+        run_at(&mut deps, "bob", 0, 20_000, end).unwrap();
+
+        let result: DrawResultResponse =
+            from_binary(&query(&deps, QueryMsg::DrawResult {}).unwrap()).unwrap();
+        assert_eq!(result.weights, vec![Uint128(30), Uint128(5)]);
+        let entropy = result.entropy.clone().unwrap();
+        let golden = draw_weighted(&seed, entropy.as_slice(), &result.weights).unwrap();
+        assert_eq!(result.winners.len(), 1);
+        assert_eq!(result.winners[0].ticket_id, golden);
+        assert_eq!(result.host_remainder, Uint128(35));
+    }
+
+    #[test]
+    fn weighted_draw_spans_large_weights() {
+        // With a 64-bit roll the second entry could never win
+        let weights = vec![Uint128(u128::MAX / 2), Uint128(u128::MAX / 2)];
+        let winners: Vec<u32> = (0..16u8)
+            .map(|i| draw_weighted(&[i], b"entropy", &weights).unwrap())
+            .collect();
+        assert!(winners.contains(&0));
+        assert!(winners.contains(&1));
+
+        assert_eq!(draw_weighted(b"seed", b"entropy", &[Uint128(0)]), None);
+    }
+
+    #[test]
+    fn refunds() {
+        let mut msg = init_msg(3, 0);
+        msg.min_tickets_sold = Some(3);
+        msg.claim_window = Some(100);
+        assert_error(try_init(msg.clone(), &coins(1000, "uscrt")), 1050);
+
+        msg.draw_at = Some(20_000);
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 0, 10);
+        buy(&mut deps, "bob", 1, 10);
+
+        let refund = HandleMsg::ClaimRefund {
+            ticket_ids: vec![0],
+        };
+        assert_error(run_at(&mut deps, "alice", 0, 20_000, refund.clone()), 1046);
+
+        let end = HandleMsg::EndLottery { reveal: None };
+        let res = run_at(&mut deps, "bob", 0, 20_000, end).unwrap();
+        assert_eq!(res.log, vec![log("refunding", "too_few_tickets")]);
+
+        let res = run_at(&mut deps, "alice", 0, 20_050, refund).unwrap();
+        assert_eq!(sends(&res), vec![send("alice", 10)]);
+
+        // The host recovers the prize
+        let remainder = HandleMsg::ClaimRemainder {};
+        let res = run_at(&mut deps, "creator", 0, 20_050, remainder).unwrap();
+        assert_eq!(sends(&res), vec![send("creator", 1000)]);
+
+        // Refunds expire with the claim window
+        let refund = HandleMsg::ClaimRefund {
+            ticket_ids: vec![1],
+        };
+        assert_error(run_at(&mut deps, "bob", 0, 20_100, refund), 1022);
+        let sweep = HandleMsg::SweepUnclaimed {};
+        let res = run_at(&mut deps, "creator", 0, 20_100, sweep).unwrap();
+        assert_eq!(sends(&res), vec![send("creator", 10)]);
+    }
+
+    #[test]
+    fn refunds_when_seed_not_revealed() {
+        let seed = b"secret seed".to_vec();
+        let mut msg = init_msg(2, 0);
+        msg.seed_commitment = Some(Binary(Sha256::digest(seed.as_slice()).to_vec()));
+        msg.draw_at = Some(20_000);
+        msg.reveal_window = Some(100);
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 0, 10);
+
+        // Once the reveal window is over nobody can be drawn anymore
+        let end = HandleMsg::EndLottery {
+            reveal: Some(Binary(seed)),
+        };
+        let res = run_at(&mut deps, "alice", 0, 20_100, end).unwrap();
+        assert_eq!(res.log, vec![log("refunding", "seed_not_revealed")]);
+
+        let refund = HandleMsg::ClaimRefund {
+            ticket_ids: vec![0],
+        };
+        let res = run_at(&mut deps, "alice", 0, 20_100, refund).unwrap();
+        assert_eq!(sends(&res), vec![send("alice", 10)]);
+    }
+
+    #[test]
+    fn min_tickets_sold_must_be_reachable() {
+        let mut msg = init_msg(3, 0);
+        msg.min_tickets_sold = Some(4);
+        msg.draw_at = Some(20_000);
+        assert_error(try_init(msg, &coins(1000, "uscrt")), 1048);
+    }
+
+    #[test]
+    fn consolation() {
+        let mut msg = init_msg(3, 0);
+        msg.consolation_bps = Some(10_001);
+        assert_error(try_init(msg, &coins(1000, "uscrt")), 1033);
+
+        let mut msg = init_msg(3, 0);
+        msg.consolation_bps = Some(5_000);
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 0, 10);
+        buy(&mut deps, "bob", 1, 10);
+        buy(&mut deps, "carol", 2, 10);
+
+        // Half of the 30 paid is split between the two losing tickets, before the draw too
+        let key = HandleMsg::SetViewingKey {
+            key: "key".to_string(),
+        };
+        run(&mut deps, "bob", 0, key).unwrap();
+        let pending = QueryMsg::PendingPayouts {
+            address: HumanAddr::from("bob"),
+            viewing_key: "key".to_string(),
+        };
+        let payouts: PendingPayoutsResponse = from_binary(&query(&deps, pending).unwrap()).unwrap();
+        assert_eq!(payouts.amount.amount, Uint128(7));
+
+        run(
+            &mut deps,
+            "creator",
+            0,
+            HandleMsg::EndLottery { reveal: None },
+        )
+        .unwrap();
+
+        let outcome = reveal(&mut deps, "bob", 1).unwrap();
+        assert!(!outcome.winner);
+        assert_eq!(outcome.value.amount, Uint128(7));
+
+        let claim = HandleMsg::ClaimPrize {
+            ticket_ids: vec![1],
+        };
+        let res = run(&mut deps, "bob", 0, claim).unwrap();
+        assert_eq!(sends(&res), vec![send("bob", 7)]);
+
+        // The rounding dust is left to the host
+        let result: DrawResultResponse =
+            from_binary(&query(&deps, QueryMsg::DrawResult {}).unwrap()).unwrap();
+        assert_eq!(result.host_remainder, Uint128(16));
+    }
+
+    #[test]
+    fn beneficiary_split() {
+        let mut msg = init_msg(2, 0);
+        msg.beneficiaries = Some(vec![BeneficiaryInfo {
+            address: HumanAddr::from("charity"),
+            bps: 2_500,
+        }]);
+        let mut deps = setup(msg);
+        buy(&mut deps, "alice", 1, 100);
+
+        // The host's pending payout already leaves out the beneficiary's share
+        let key = HandleMsg::SetViewingKey {
+            key: "key".to_string(),
+        };
+        run(&mut deps, "creator", 0, key).unwrap();
+        let pending = QueryMsg::PendingPayouts {
+            address: HumanAddr::from("creator"),
+            viewing_key: "key".to_string(),
+        };
+        let payouts: PendingPayoutsResponse = from_binary(&query(&deps, pending).unwrap()).unwrap();
+        assert_eq!(payouts.host_remainder, Uint128(825));
+
+        let end = HandleMsg::EndLottery { reveal: None };
+        let res = run(&mut deps, "creator", 0, end).unwrap();
+        assert_eq!(sends(&res), vec![send("charity", 275)]);
+        assert_eq!(res.log, vec![log("beneficiary", "charity:275")]);
+
+        let result: DrawResultResponse =
+            from_binary(&query(&deps, QueryMsg::DrawResult {}).unwrap()).unwrap();
+        assert_eq!(result.host_remainder, Uint128(825));
+        assert_eq!(
+            result.beneficiary_payouts,
+            vec![BeneficiaryPayoutResponse {
+                address: HumanAddr::from("charity"),
+                amount: Uint128(275),
+            }]
+        );
+
+        let res = run(&mut deps, "creator", 0, HandleMsg::ClaimRemainder {}).unwrap();
+        assert_eq!(sends(&res), vec![send("creator", 825)]);
+    }
+
+    #[test]
+    fn too_many_beneficiaries() {
+        let mut msg = init_msg(2, 0);
+        msg.beneficiaries = Some(
+            (0..=MAX_BENEFICIARIES)
+                .map(|i| BeneficiaryInfo {
+                    address: HumanAddr(format!("charity{}", i)),
+                    bps: 100,
+                })
+                .collect(),
+        );
+        assert_error(try_init(msg, &coins(1000, "uscrt")), 1051);
+    }
+}
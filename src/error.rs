@@ -0,0 +1,249 @@
+use cosmwasm_std::{HumanAddr, StdError, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+
+/// All the errors the lottery contract can return.
+///
+/// Each variant has a stable numeric code (see `ContractError::code`). The code is
+/// prefixed to the message of the `StdError` we return, so clients can match on it
+/// instead of on the wording.
+#[derive(Snafu, Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractError {
+    #[snafu(display("Ticket {} does not exist", ticket_id))]
+    InvalidTicket { ticket_id: u32 },
+
+    #[snafu(display("Ticket {} has been redeemed already", ticket_id))]
+    AlreadyRedeemed { ticket_id: u32 },
+
+    #[snafu(display("Ticket {} has already been sold", ticket_id))]
+    TicketSold { ticket_id: u32 },
+
+    #[snafu(display("{} is not the owner of ticket {}", address, ticket_id))]
+    NotOwner { address: HumanAddr, ticket_id: u32 },
+
+    #[snafu(display("Sender is not allowed to perform this action"))]
+    Unauthorized {},
+
+    #[snafu(display("No funds were sent"))]
+    NoFunds {},
+
+    #[snafu(display("Insufficient funds: required {}, sent {}", required, sent))]
+    InsufficientFunds { required: Uint128, sent: Uint128 },
+
+    #[snafu(display("Wrong denomination: expected {}, got {}", expected, got))]
+    WrongDenom { expected: String, got: String },
+
+    #[snafu(display("The lottery is closed"))]
+    LotteryClosed {},
+
+    #[snafu(display("The zero address can't be used here"))]
+    ZeroAddress {},
+
+    #[snafu(display("Arithmetic overflow"))]
+    Overflow {},
+
+    #[snafu(display("Payout of {} exceeds the deposit of {}", payout, deposit))]
+    PayoutExceedsDeposit { payout: Uint128, deposit: Uint128 },
+
+    #[snafu(display("Ticket count must be between 1 and {}, got {}", max, count))]
+    InvalidTicketCount { count: u32, max: u32 },
+
+    #[snafu(display(
+        "Golden ticket {} is out of range for {} tickets",
+        golden,
+        ticket_count
+    ))]
+    InvalidGolden { golden: u32, ticket_count: u32 },
+
+    #[snafu(display("Only a single coin can be sent"))]
+    MultipleCoins {},
+
+    #[snafu(display("The winning prize must be greater than zero"))]
+    ZeroPrize {},
+
+    #[snafu(display("Native funds can't be sent to a SNIP-20 lottery"))]
+    UnexpectedFunds {},
+
+    #[snafu(display("Receive was called without a message"))]
+    MissingReceiveMsg {},
+
+    #[snafu(display("The prize has not been funded yet"))]
+    NotFunded {},
+
+    #[snafu(display("The lottery has not been drawn yet"))]
+    NotDrawn {},
+
+    #[snafu(display("There is nothing to claim"))]
+    NothingToClaim {},
+
+    #[snafu(display("Prizes could only be claimed until block {}", deadline))]
+    ClaimWindowClosed { deadline: u64 },
+
+    #[snafu(display("Prizes can still be claimed until block {}", deadline))]
+    ClaimWindowOpen { deadline: u64 },
+
+    #[snafu(display("This lottery has no claim window"))]
+    NoClaimWindow {},
+
+    #[snafu(display("Wrong viewing key for this address"))]
+    InvalidViewingKey {},
+
+    #[snafu(display("The revealed seed doesn't match the commitment"))]
+    InvalidReveal {},
+
+    #[snafu(display("Ticket {} is not listed for sale", ticket_id))]
+    NotListed { ticket_id: u32 },
+
+    #[snafu(display("The price must be greater than zero"))]
+    ZeroPrice {},
+
+    #[snafu(display("Wrong payment: price is {}, sent {}", price, sent))]
+    WrongPrice { price: Uint128, sent: Uint128 },
+
+    #[snafu(display("There is no offer on ticket {}", ticket_id))]
+    NoOffer { ticket_id: u32 },
+
+    #[snafu(display("Offer must be higher than {}, sent {}", current, sent))]
+    OfferTooLow { current: Uint128, sent: Uint128 },
+
+    #[snafu(display("Ticket {} is not held by anyone yet", ticket_id))]
+    NotOnSecondaryMarket { ticket_id: u32 },
+
+    #[snafu(display("{} basis points is more than 100%", bps))]
+    InvalidBasisPoints { bps: u16 },
+
+    #[snafu(display("The draw must be after the current block, got {}", draw_at))]
+    InvalidDrawHeight { draw_at: u64 },
+
+    #[snafu(display("The lottery can't be drawn before block {}", draw_at))]
+    TooEarly { draw_at: u64 },
+
+    #[snafu(display("Tickets are frozen since block {}", frozen_from))]
+    TransfersFrozen { frozen_from: u64 },
+
+    #[snafu(display("This action is not available in the lottery's game mode"))]
+    WrongMode {},
+
+    #[snafu(display("The loot table must have loot with a positive weight and supply"))]
+    InvalidLootTable {},
+
+    #[snafu(display("Everything has been sold"))]
+    SoldOut {},
+
+    #[snafu(display("A prng_seed is required in this game mode"))]
+    MissingPrngSeed {},
+
+    #[snafu(display("Tickets can't be revealed before sales close"))]
+    NotRevealedYet {},
+
+    #[snafu(display(
+        "Scratch tiers must pay within the pool, with odds adding up to 100% at most"
+    ))]
+    InvalidScratchTiers {},
+
+    #[snafu(display(
+        "Lotto tiers must need between 1 and pick_count matches, within 100% of the pool"
+    ))]
+    InvalidLottoTiers {},
+
+    #[snafu(display("Pick {} different numbers from 1 to {}", pick_count, max_number))]
+    InvalidPicks { pick_count: u8, max_number: u8 },
+
+    #[snafu(display("A seed_commitment is required in this game mode"))]
+    MissingSeedCommitment {},
+
+    #[snafu(display("The lottery is not refunding tickets"))]
+    NotRefunding {},
+
+    #[snafu(display("A draw_at height is required with a seed_commitment"))]
+    MissingDrawHeight {},
+
+    #[snafu(display(
+        "Minimum of {} tickets sold can't be met with {} tickets",
+        min_tickets_sold,
+        ticket_count
+    ))]
+    InvalidMinTickets {
+        min_tickets_sold: u32,
+        ticket_count: u32,
+    },
+
+    #[snafu(display("A draw_at height is required with freeze_blocks"))]
+    FreezeWithoutDrawHeight {},
+
+    #[snafu(display("A draw_at height is required with min_tickets_sold"))]
+    MinTicketsWithoutDrawHeight {},
+
+    #[snafu(display("{} beneficiaries is more than the maximum of {}", count, max))]
+    TooManyBeneficiaries { count: u32, max: u32 },
+}
+
+impl ContractError {
+    /// Stable code identifying the error. Never reuse or renumber these.
+    pub fn code(&self) -> u16 {
+        match self {
+            ContractError::InvalidTicket { .. } => 1001,
+            ContractError::AlreadyRedeemed { .. } => 1002,
+            ContractError::TicketSold { .. } => 1003,
+            ContractError::NotOwner { .. } => 1004,
+            ContractError::Unauthorized { .. } => 1005,
+            ContractError::NoFunds { .. } => 1006,
+            ContractError::InsufficientFunds { .. } => 1007,
+            ContractError::WrongDenom { .. } => 1008,
+            ContractError::LotteryClosed { .. } => 1009,
+            ContractError::ZeroAddress { .. } => 1010,
+            ContractError::Overflow { .. } => 1011,
+            ContractError::PayoutExceedsDeposit { .. } => 1012,
+            ContractError::InvalidTicketCount { .. } => 1013,
+            ContractError::InvalidGolden { .. } => 1014,
+            ContractError::MultipleCoins { .. } => 1015,
+            ContractError::ZeroPrize { .. } => 1016,
+            ContractError::UnexpectedFunds { .. } => 1017,
+            ContractError::MissingReceiveMsg { .. } => 1018,
+            ContractError::NotFunded { .. } => 1019,
+            ContractError::NotDrawn { .. } => 1020,
+            ContractError::NothingToClaim { .. } => 1021,
+            ContractError::ClaimWindowClosed { .. } => 1022,
+            ContractError::ClaimWindowOpen { .. } => 1023,
+            ContractError::NoClaimWindow { .. } => 1024,
+            ContractError::InvalidViewingKey { .. } => 1025,
+            ContractError::InvalidReveal { .. } => 1026,
+            ContractError::NotListed { .. } => 1027,
+            ContractError::ZeroPrice { .. } => 1028,
+            ContractError::WrongPrice { .. } => 1029,
+            ContractError::NoOffer { .. } => 1030,
+            ContractError::OfferTooLow { .. } => 1031,
+            ContractError::NotOnSecondaryMarket { .. } => 1032,
+            ContractError::InvalidBasisPoints { .. } => 1033,
+            ContractError::InvalidDrawHeight { .. } => 1034,
+            ContractError::TooEarly { .. } => 1035,
+            ContractError::TransfersFrozen { .. } => 1036,
+            ContractError::WrongMode { .. } => 1037,
+            ContractError::InvalidLootTable { .. } => 1038,
+            ContractError::SoldOut { .. } => 1039,
+            ContractError::MissingPrngSeed { .. } => 1040,
+            ContractError::NotRevealedYet { .. } => 1041,
+            ContractError::InvalidScratchTiers { .. } => 1042,
+            ContractError::InvalidLottoTiers { .. } => 1043,
+            ContractError::InvalidPicks { .. } => 1044,
+            ContractError::MissingSeedCommitment { .. } => 1045,
+            ContractError::NotRefunding { .. } => 1046,
+            ContractError::MissingDrawHeight { .. } => 1047,
+            ContractError::InvalidMinTickets { .. } => 1048,
+            ContractError::FreezeWithoutDrawHeight { .. } => 1049,
+            ContractError::MinTicketsWithoutDrawHeight { .. } => 1050,
+            ContractError::TooManyBeneficiaries { .. } => 1051,
+        }
+    }
+}
+
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        StdError::GenericErr {
+            msg: format!("[{}] {}", err.code(), err),
+            backtrace: None,
+        }
+    }
+}
@@ -1,12 +1,74 @@
-use cosmwasm_std::HumanAddr;
+use cosmwasm_std::{Binary, Coin, HumanAddr, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::state::{GameMode, LotteryStatus};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InitMsg {
     pub name: String,
     pub ticket_count: u32,
+    /// Index of the golden ticket. Ignored when `seed_commitment` is set.
     pub golden: u32,
+    /// Denom of the prize and ticket payments, defaults to `uscrt`
+    pub denom: Option<String>,
+    /// Pay with a SNIP-20 token instead of native coins. `denom` is ignored in that case.
+    pub token: Option<TokenInfo>,
+    /// Prize amount for SNIP-20 lotteries, which the host funds with `ReceiveMsg::FundPrize`
+    pub prize: Option<Uint128>,
+    /// Blocks after the draw, or the cancellation, during which prizes and refunds can be
+    /// claimed. Claims never expire if not set.
+    pub claim_window: Option<u64>,
+    /// Receives the unclaimed prizes once the claim window is over, the host if not set
+    pub treasury: Option<HumanAddr>,
+    /// SHA-256 hash of a secret seed. If set, the golden ticket is drawn from the seed, revealed
+    /// with `EndLottery`, and the entropy of every purchase. Lotto numbers, raffle and weighted
+    /// winners are drawn the same way.
+    pub seed_commitment: Option<Binary>,
+    /// Royalty taken on secondary sales, in basis points
+    pub royalty_bps: Option<u16>,
+    /// Receives the royalties, which are added to the host's payout if not set
+    pub royalty_recipient: Option<HumanAddr>,
+    /// Block height from which `EndLottery` can be called, any time if not set. Sales close at
+    /// this height. Required with `seed_commitment`, except for scratch cards.
+    pub draw_at: Option<u64>,
+    /// Blocks after `draw_at` during which the host can reveal the committed seed, defaults to
+    /// 14400. The lottery refunds its buyers if the seed isn't revealed by then.
+    pub reveal_window: Option<u64>,
+    /// Blocks before `draw_at` during which transfers and sales are rejected. Requires `draw_at`.
+    pub freeze_blocks: Option<u64>,
+    /// Defaults to the classic golden ticket lottery
+    pub mode: Option<GameMode>,
+    /// Minimum paid for a ticket or a lootbox, defaults to 1
+    pub ticket_price: Option<Uint128>,
+    /// Secret seed of the contract's randomness, used to fill lootboxes and scratch cards.
+    /// Scratch cards also need its hash as `seed_commitment`, it's published at the draw.
+    pub prng_seed: Option<Binary>,
+    /// Only let owners reveal their tickets once sales are closed
+    pub reveal_after_close: Option<bool>,
+    /// Tickets that must be sold by `EndLottery`, below that the lottery is cancelled and
+    /// buyers are refunded. Requires `draw_at`, not available for lootboxes and scratch cards.
+    pub min_tickets_sold: Option<u32>,
+    /// Share of the ticket revenue split evenly among the sold tickets that don't win, in basis
+    /// points. Not available for lootboxes and scratch cards.
+    pub consolation_bps: Option<u16>,
+    /// Paid their share of what's left for the host as soon as the lottery is drawn. The host
+    /// keeps whatever isn't shared. At most `MAX_BENEFICIARIES`.
+    pub beneficiaries: Option<Vec<BeneficiaryInfo>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BeneficiaryInfo {
+    pub address: HumanAddr,
+    /// Share of the host's remainder, in basis points
+    pub bps: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenInfo {
+    pub address: HumanAddr,
+    /// Code hash of this contract, registered with the token for `Receive` callbacks
+    pub code_hash: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -19,16 +81,167 @@ pub enum HandleMsg {
     },
     BuyTicket {
         ticket_id: u32,
+        /// Secret mixed into the draw, see `entropy_entry`. Purchases without one can be
+        /// predicted by whoever knows the seed.
+        entropy: Option<Binary>,
+    },
+    /// Lootbox mode, buy a box with a random prize
+    BuyLootbox {
+        entropy: Option<Binary>,
+    },
+    /// Lootbox mode, get paid the content of an owned box
+    OpenLootbox {
+        ticket_id: u32,
+    },
+    /// Scratch card mode, buy a card. Its outcome is the response's data, winnings can be
+    /// claimed right away with `ClaimPrize`.
+    BuyScratchCard {
+        entropy: Option<Binary>,
+    },
+    /// Lotto mode, buy a ticket with the given numbers
+    BuyLottoTicket {
+        numbers: Vec<u8>,
+        entropy: Option<Binary>,
+    },
+    /// Raffle mode, buy a new ticket
+    BuyRaffleTicket {
+        entropy: Option<Binary>,
+    },
+    /// Weighted mode, add the funds sent to the sender's entry
+    Contribute {
+        entropy: Option<Binary>,
+    },
+    /// Owner only, tells whether the ticket is a winner. The answer is the response's data.
+    RevealTicket {
+        ticket_id: u32,
+    },
+    /// Anyone can end the lottery from `draw_at` on, only the host can if it's not set
+    EndLottery {
+        /// The seed matching `seed_commitment`, if one was committed
+        reveal: Option<Binary>,
+    },
+    /// Withdraw the value of owned tickets after the draw, burning them
+    ClaimPrize {
+        ticket_ids: Vec<u32>,
+    },
+    /// Host only, withdraw what's left of the deposit after the draw
+    ClaimRemainder {},
+    /// Refunds what was paid for the given tickets, if too few tickets were sold
+    ClaimRefund {
+        ticket_ids: Vec<u32>,
+    },
+    /// Host only, push the payouts of up to `limit` tickets after the draw
+    SettleBatch {
+        limit: u32,
+    },
+    /// Host or treasury only, recover the unclaimed prizes once the claim window is over
+    SweepUnclaimed {},
+    /// Set the key used to authenticate the sender's private queries
+    SetViewingKey {
+        key: String,
+    },
+    /// Put an owned ticket up for sale, in the lottery's denom
+    ListTicket {
+        ticket_id: u32,
+        price: Uint128,
+    },
+    Delist {
+        ticket_id: u32,
+    },
+    /// Buy a listed ticket by sending exactly its price
+    BuyListed {
+        ticket_id: u32,
+    },
+    /// Bid on a ticket with the funds sent, which are escrowed until accepted or refunded
+    MakeOffer {
+        ticket_id: u32,
+    },
+    CancelOffer {
+        ticket_id: u32,
+    },
+    /// Owner only, sell the ticket to the current offer
+    AcceptOffer {
+        ticket_id: u32,
+    },
+    /// SNIP-20 `Send` callback
+    Receive {
+        sender: HumanAddr,
+        from: HumanAddr,
+        amount: Uint128,
+        msg: Option<Binary>,
+    },
+}
+
+/// Messages embedded in a SNIP-20 `Send` to this contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    BuyTicket {
+        ticket_id: u32,
+        entropy: Option<Binary>,
+    },
+    FundPrize {},
+    BuyLootbox {
+        entropy: Option<Binary>,
+    },
+    BuyScratchCard {
+        entropy: Option<Binary>,
+    },
+    BuyLottoTicket {
+        numbers: Vec<u8>,
+        entropy: Option<Binary>,
+    },
+    BuyRaffleTicket {
+        entropy: Option<Binary>,
+    },
+    Contribute {
+        entropy: Option<Binary>,
+    },
+    BuyListed {
+        ticket_id: u32,
+    },
+    MakeOffer {
+        ticket_id: u32,
+    },
+}
+
+/// The subset of the SNIP-20 handle messages we send to the token contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Snip20Msg {
+    Transfer {
+        recipient: HumanAddr,
+        amount: Uint128,
+    },
+    RegisterReceive {
+        code_hash: String,
     },
-    EndLottery {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     // GetCount returns the current count as a json-encoded number
-    BalanceOf { owner: HumanAddr },
-    OwnerOf { ticket_id: u32 },
+    BalanceOf {
+        owner: HumanAddr,
+    },
+    OwnerOf {
+        ticket_id: u32,
+    },
+    UnclaimedPrizes {},
+    PendingPayouts {
+        address: HumanAddr,
+        viewing_key: String,
+    },
+    DrawResult {},
+    Listings {},
+    Offers {},
+    Info {},
+    RevealTicket {
+        ticket_id: u32,
+        /// Viewing key of the ticket's owner
+        viewing_key: String,
+    },
 }
 
 // We define a custom struct for each query response
@@ -36,3 +249,91 @@ pub enum QueryMsg {
 pub struct CountResponse {
     pub count: i32,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnclaimedPrize {
+    pub ticket_id: u32,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnclaimedResponse {
+    pub prizes: Vec<UnclaimedPrize>,
+    pub host_remainder: Uint128,
+    /// Block from which prizes can no longer be claimed, if they expire
+    pub claim_deadline: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPayoutsResponse {
+    /// Tickets held by the address that haven't been paid yet
+    pub ticket_ids: Vec<u32>,
+    /// Part of `amount` coming from the host's remainder
+    pub host_remainder: Uint128,
+    /// Total the address would receive
+    pub amount: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WinningTicketResponse {
+    pub ticket_id: u32,
+    pub tier: u32,
+    /// Holder at the time of the draw, `None` if the ticket wasn't sold
+    pub owner: Option<HumanAddr>,
+    pub payout: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DrawResultResponse {
+    pub winners: Vec<WinningTicketResponse>,
+    pub host_remainder: Uint128,
+    pub drawn_at: u64,
+    pub seed: Option<Binary>,
+    pub entropy: Option<Binary>,
+    pub entries: Vec<Binary>,
+    pub numbers: Vec<u8>,
+    pub weights: Vec<Uint128>,
+    pub beneficiary_payouts: Vec<BeneficiaryPayoutResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BeneficiaryPayoutResponse {
+    pub address: HumanAddr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListingResponse {
+    pub ticket_id: u32,
+    pub seller: HumanAddr,
+    pub price: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OfferResponse {
+    pub ticket_id: u32,
+    pub bidder: HumanAddr,
+    pub amount: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InfoResponse {
+    pub name: String,
+    pub host: HumanAddr,
+    pub ticket_count: u32,
+    pub winning_prize: Coin,
+    pub status: LotteryStatus,
+    pub claim_window: Option<u64>,
+    pub draw_at: Option<u64>,
+    pub freeze_blocks: u64,
+    pub royalty_bps: u16,
+    pub royalty_recipient: HumanAddr,
+    pub beneficiaries: Vec<BeneficiaryInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RevealResponse {
+    pub ticket_id: u32,
+    pub winner: bool,
+    pub value: Coin,
+}
@@ -1,26 +1,228 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{CanonicalAddr, Coin, Storage, Uint128};
-use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use cosmwasm_std::{Binary, CanonicalAddr, Coin, Storage, Uint128};
+use cosmwasm_storage::{
+    bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton,
+    Singleton,
+};
 
 pub static CONFIG_KEY: &[u8] = b"config";
+pub static VIEWING_KEY_PREFIX: &[u8] = b"viewing_keys";
+pub static DRAW_RESULT_KEY: &[u8] = b"draw_result";
+pub static SEALED_KEY: &[u8] = b"sealed";
+/// Default denom of the prize and tickets, used when `InitMsg` doesn't set one
 pub static USCRT_DENOM: &str = "uscrt";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Ticket {
     pub id: u32,
-    pub value: Coin,
     pub owner: CanonicalAddr,
     pub approved: Vec<CanonicalAddr>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LotteryStatus {
+    /// Tickets are on sale
+    Open,
+    /// The outcome is fixed and prizes can be claimed
+    Drawn,
+    /// Every payout was pushed by `SettleBatch`
+    Settled,
+    /// Too few tickets were sold, buyers get their payment back with `ClaimRefund`
+    Refunding,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
+    pub name: String,
     pub items: Vec<Ticket>,
     pub contract_owner: CanonicalAddr,
+    /// Address of this contract, which holds the unsold tickets. Queries don't get an `Env`.
+    pub contract_addr: CanonicalAddr,
     pub winning_prize: Coin,
     pub deposit: Uint128,
+    pub status: LotteryStatus,
+    /// What the host can claim after the draw
+    pub host_remainder: Uint128,
+    /// Next ticket to be processed by `SettleBatch`
+    pub settle_cursor: u32,
+    /// Block height of the draw, or of the cancellation of a refunded lottery
+    pub drawn_at: Option<u64>,
+    /// Blocks after the draw during which prizes and refunds can be claimed, forever if not set
+    pub claim_window: Option<u64>,
+    /// Receives the unclaimed prizes on `SweepUnclaimed`, the host if not set
+    pub treasury: Option<CanonicalAddr>,
+    /// Denom of all the coins handled by the lottery. For SNIP-20 lotteries this is the
+    /// token's address.
+    pub denom: String,
+    /// SNIP-20 token used instead of native coins, if any
+    pub token: Option<CanonicalAddr>,
+    /// Whether the prize was funded, at init for native lotteries and by `FundPrize` otherwise.
+    /// Prizes paid during sales lower the deposit, so it can't tell.
+    pub prize_funded: bool,
+    /// SHA-256 hash of the host's seed, revealed at the draw
+    pub seed_commitment: Option<Binary>,
+    /// Hash chain of the commitment and every purchase
+    pub entropy: Binary,
+    /// Tickets their owners put up for sale
+    pub listings: Vec<Listing>,
+    /// Escrowed bids on tickets, at most one per ticket
+    pub offers: Vec<Offer>,
+    /// Royalty on secondary sales, in basis points
+    pub royalty_bps: u16,
+    /// Receives the royalties, if not set they go to the host's payout
+    pub royalty_recipient: Option<CanonicalAddr>,
+    /// Block height from which the lottery can be drawn, any time if not set
+    pub draw_at: Option<u64>,
+    /// Blocks before `draw_at` during which tickets can't change hands
+    pub freeze_blocks: u64,
+    pub mode: GameMode,
+    /// Minimum paid for a ticket or a lootbox
+    pub ticket_price: Uint128,
+    /// Private seed of the contract's randomness
+    pub prng_seed: Binary,
+    /// How many of each loot of the loot table were issued
+    pub loot_issued: Vec<u32>,
+    /// Only let owners reveal their tickets once sales are closed
+    pub reveal_after_close: bool,
+    /// Total won by scratch cards so far, capped at the prize pool
+    pub instant_awarded: Uint128,
+    /// Tickets that must be sold for the draw to happen, otherwise every buyer is refunded
+    pub min_tickets_sold: u32,
+    /// Share of the ticket revenue split among the sold tickets that didn't win, in basis points
+    pub consolation_bps: u16,
+    /// Blocks after `draw_at` during which the host can reveal the seed, refunding buyers after
+    pub reveal_window: u64,
+    /// Receive their share of the host's remainder when the lottery is drawn
+    pub beneficiaries: Vec<Beneficiary>,
+}
+
+/// What each ticket is worth, stored apart from `State` so the values never go out with it.
+/// No query returns these except to the owner of a ticket.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SealedTickets {
+    /// Value of each ticket in the lottery's denom, by ticket id
+    pub values: Vec<Uint128>,
+    /// The golden ticket, once known
+    pub golden: Option<u32>,
+    /// Numbers picked for each lotto ticket, by ticket id
+    pub picks: Vec<Vec<u8>>,
+    /// Amount contributed to each weighted entry, by ticket id
+    pub weights: Vec<Uint128>,
+    /// Amount paid for each ticket, refunded if too few tickets are sold
+    pub paid: Vec<Uint128>,
+    /// Buyer of each scratch card, by ticket id
+    pub buyers: Vec<CanonicalAddr>,
+    /// Every entry mixed into the entropy, in order. Only published by the draw.
+    pub entries: Vec<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    /// A single golden ticket among `ticket_count` tickets created at init
+    Classic,
+    /// Every purchase mints a box holding a prize drawn from the loot table
+    Lootbox { loot_table: Vec<Loot> },
+    /// Every purchase mints a card that wins one of the tiers instantly, or nothing
+    ScratchCard { tiers: Vec<ScratchTier> },
+    /// Buyers pick `pick_count` numbers from 1 to `max_number`, and the tiers are paid by the
+    /// number of matches with the numbers drawn
+    Lotto {
+        pick_count: u8,
+        max_number: u8,
+        tiers: Vec<LottoTier>,
+    },
+    /// Tickets are minted on purchase and the winner is drawn among them
+    Raffle,
+    /// Every address holds a single entry, whose odds are proportional to what it contributed
+    Weighted,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Loot {
+    pub amount: Uint128,
+    /// Relative odds of drawing this loot
+    pub weight: u32,
+    /// How many of this loot can be issued
+    pub supply: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScratchTier {
+    pub amount: Uint128,
+    /// Odds of a card winning this tier, in parts per million
+    pub odds: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Beneficiary {
+    pub address: CanonicalAddr,
+    /// Share of the host's remainder, in basis points
+    pub bps: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BeneficiaryPayout {
+    pub address: CanonicalAddr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LottoTier {
+    /// Matches needed to win this tier
+    pub matches: u8,
+    /// Share of the prize pool split among the tier's winners, in basis points
+    pub share_bps: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Listing {
+    pub ticket_id: u32,
+    pub seller: CanonicalAddr,
+    /// In the lottery's denom
+    pub price: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Offer {
+    pub ticket_id: u32,
+    pub bidder: CanonicalAddr,
+    /// Escrowed by the contract, in the lottery's denom
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WinningTicket {
+    pub ticket_id: u32,
+    /// Prize tier, 0 being the golden ticket
+    pub tier: u32,
+    /// Holder at the time of the draw, or buyer of a scratch card claimed before it. `None` if
+    /// the ticket wasn't sold.
+    pub owner: Option<CanonicalAddr>,
+    pub payout: Coin,
+}
+
+/// Outcome of the draw, kept after settlement so results can be published and audited
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DrawResult {
+    pub winners: Vec<WinningTicket>,
+    pub host_remainder: Uint128,
+    pub drawn_at: u64,
+    /// Seed the draw was derived from, if any
+    pub seed: Option<Binary>,
+    /// Final entropy the seed was mixed with, if the draw used a committed seed
+    pub entropy: Option<Binary>,
+    /// Entries mixed into the entropy, in order, published along with the seed
+    pub entries: Vec<Binary>,
+    /// Winning numbers of a lotto
+    pub numbers: Vec<u8>,
+    /// Weight of every entry of a weighted draw, by ticket id, unsold ones count as 0
+    pub weights: Vec<Uint128>,
+    /// What each beneficiary was paid at the draw
+    pub beneficiary_payouts: Vec<BeneficiaryPayout>,
 }
 
 pub fn config<S: Storage>(storage: &mut S) -> Singleton<S, State> {
@@ -30,3 +232,28 @@ pub fn config<S: Storage>(storage: &mut S) -> Singleton<S, State> {
 pub fn config_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, State> {
     singleton_read(storage, CONFIG_KEY)
 }
+
+/// SHA-256 hashes of the viewing keys, by canonical address
+pub fn viewing_keys<S: Storage>(storage: &mut S) -> Bucket<S, Binary> {
+    bucket(VIEWING_KEY_PREFIX, storage)
+}
+
+pub fn viewing_keys_read<S: Storage>(storage: &S) -> ReadonlyBucket<S, Binary> {
+    bucket_read(VIEWING_KEY_PREFIX, storage)
+}
+
+pub fn draw_result<S: Storage>(storage: &mut S) -> Singleton<S, DrawResult> {
+    singleton(storage, DRAW_RESULT_KEY)
+}
+
+pub fn draw_result_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, DrawResult> {
+    singleton_read(storage, DRAW_RESULT_KEY)
+}
+
+pub fn sealed<S: Storage>(storage: &mut S) -> Singleton<S, SealedTickets> {
+    singleton(storage, SEALED_KEY)
+}
+
+pub fn sealed_read<S: Storage>(storage: &S) -> ReadonlySingleton<S, SealedTickets> {
+    singleton_read(storage, SEALED_KEY)
+}